@@ -1,6 +1,8 @@
 use actix_web::{App, HttpResponse, HttpServer, post, web};
 use nihility_gsv::error::*;
+use nihility_gsv::text::TextLanguage;
 use nihility_gsv::{NihilityGsv, NihilityGsvConfig, NihilityGsvInferParam};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
@@ -15,8 +17,84 @@ async fn infer(
     gsv: web::Data<Mutex<NihilityGsv>>,
     json: web::Json<NihilityGsvInferParam>,
 ) -> Result<HttpResponse> {
-    let audio = gsv.lock().await.infer_out_to_wav(json.into_inner())?;
-    Ok(HttpResponse::Ok().content_type("audio/wav").body(audio))
+    let (audio, report) = gsv
+        .lock()
+        .await
+        .infer_out_to_wav_with_report(json.into_inner())?;
+    let mut response = HttpResponse::Ok();
+    response.content_type("audio/wav");
+    if !report.text.is_empty() {
+        response.insert_header(("x-text", header_value(&report.text.join("; "))));
+    }
+    if !report.warnings.is_empty() {
+        response.insert_header(("x-text-warnings", header_value(&report.warnings.join("; "))));
+    }
+    Ok(response.body(audio))
+}
+
+/// well under the 8 KB header limit of most servers and proxies
+const MAX_HEADER_BYTES: usize = 4096;
+
+/// utf-8 percent-encoded for `decodeURIComponent`, a long text is cut at a char boundary, the
+/// whole rewritten text comes from `/rewrite`
+fn header_value(text: &str) -> String {
+    let mut value = String::new();
+    for c in text.chars() {
+        let encoded = utf8_percent_encode(c.encode_utf8(&mut [0; 4]), NON_ALPHANUMERIC).to_string();
+        if value.len() + encoded.len() > MAX_HEADER_BYTES {
+            break;
+        }
+        value.push_str(&encoded);
+    }
+    value
+}
+
+#[derive(Debug, Deserialize)]
+struct AddWordParam {
+    word: String,
+    freq: Option<usize>,
+    tag: Option<String>,
+}
+
+#[post("/word")]
+async fn add_word(
+    gsv: web::Data<Mutex<NihilityGsv>>,
+    json: web::Json<AddWordParam>,
+) -> Result<HttpResponse> {
+    let param = json.into_inner();
+    let freq = gsv
+        .lock()
+        .await
+        .add_word(&param.word, param.freq, param.tag.as_deref());
+    Ok(HttpResponse::Ok().json(freq))
+}
+
+#[derive(Debug, Deserialize)]
+struct RewriteParam {
+    text: String,
+    #[serde(default)]
+    language: TextLanguage,
+    voice: Option<String>,
+}
+
+/// the text after the rewrite rules, without reading it
+#[post("/rewrite")]
+async fn rewrite(
+    gsv: web::Data<Mutex<NihilityGsv>>,
+    json: web::Json<RewriteParam>,
+) -> Result<HttpResponse> {
+    let param = json.into_inner();
+    let text = gsv
+        .lock()
+        .await
+        .rewrite(&param.text, param.language, param.voice.as_deref());
+    Ok(HttpResponse::Ok().json(text))
+}
+
+#[post("/reload")]
+async fn reload(gsv: web::Data<Mutex<NihilityGsv>>) -> Result<HttpResponse> {
+    gsv.lock().await.reload_dict()?;
+    Ok(HttpResponse::Ok().finish())
 }
 
 #[actix_web::main]
@@ -32,10 +110,17 @@ async fn main() -> std::io::Result<()> {
         .expect("Failed to init gsv");
     let gsv = web::Data::new(Mutex::new(gsv));
 
-    HttpServer::new(move || App::new().app_data(gsv.clone()).service(infer))
-        .bind((gsv_api_config.server_addr, gsv_api_config.server_port))?
-        .run()
-        .await
+    HttpServer::new(move || {
+        App::new()
+            .app_data(gsv.clone())
+            .service(infer)
+            .service(reload)
+            .service(add_word)
+            .service(rewrite)
+    })
+    .bind((gsv_api_config.server_addr, gsv_api_config.server_port))?
+    .run()
+    .await
 }
 
 impl Default for NihilityGsvApiConfig {
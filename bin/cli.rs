@@ -1,6 +1,7 @@
 use chrono::Local;
 use clap::Parser;
-use nihility_gsv::{NihilityGsvConfig, NihilityGsvInferParam, tch};
+use nihility_gsv::text::TextLanguage;
+use nihility_gsv::{NihilityGsvConfig, NihilityGsvInferParam, TextFormat, tch};
 use std::io::Write;
 use std::path::Path;
 use std::{fs, io};
@@ -15,6 +16,12 @@ struct CliArgs {
     output_dir: String,
     #[arg(short, long)]
     text: Option<String>,
+    /// auto, zh, en, ja, ko, yue, all_zh, all_ja, all_ko or all_yue
+    #[arg(short, long, default_value = "auto")]
+    language: TextLanguage,
+    /// read the text as SSML
+    #[arg(long)]
+    ssml: bool,
 }
 
 fn main() {
@@ -71,6 +78,13 @@ impl From<CliArgs> for NihilityGsvInferParam {
         NihilityGsvInferParam {
             text: value.text.expect("infer text not init"),
             top_k: value.top_k,
+            language: value.language,
+            text_format: if value.ssml {
+                TextFormat::Ssml
+            } else {
+                TextFormat::Plain
+            },
+            ..Default::default()
         }
     }
 }
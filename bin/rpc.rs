@@ -9,7 +9,7 @@ use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
 use tonic::codegen::tokio_stream::{Stream, StreamExt};
 use tonic::transport::Server;
 use tonic::{Code, Request, Response, Status, Streaming};
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 
 const CHANNEL_CAPACITY: usize = 100;
 
@@ -173,6 +173,8 @@ async fn main() {
         .expect("could not get inner config")
         .init(device)
         .expect("Failed to init gsv");
+    #[cfg(unix)]
+    reload_on_hangup();
     Server::builder()
         .add_service(ExecuteServer::new(NihilityGsvRpcServer {
             gsv: Mutex::new(gsv),
@@ -189,6 +191,21 @@ async fn main() {
         .expect("Grpc Start Run Fail")
 }
 
+/// The rpc has no call to reload the user dictionaries, a SIGHUP does it
+#[cfg(unix)]
+fn reload_on_hangup() {
+    use tokio::signal::unix::{SignalKind, signal};
+    let mut hangup = signal(SignalKind::hangup()).expect("could not listen to SIGHUP");
+    spawn(async move {
+        while hangup.recv().await.is_some() {
+            match nihility_gsv::text::dict::reload() {
+                Ok(()) => info!("reload user dict"),
+                Err(e) => error!("reload user dict error: {}", e),
+            }
+        }
+    });
+}
+
 impl Default for NihilityGsvRpcConfig {
     fn default() -> Self {
         NihilityGsvRpcConfig {
@@ -0,0 +1,61 @@
+//! Post-processing of the 32k output samples
+
+/// 20ms at 32k
+const FRAME: usize = 640;
+const HOP: usize = FRAME / 2;
+/// how far a frame may move to line up with the previous one
+const TOLERANCE: usize = 160;
+
+/// Change the speed and keep the pitch with WSOLA, a `rate` of 2.0 is twice as fast
+pub fn time_stretch(samples: &[f32], rate: f32) -> Vec<f32> {
+    let rate = rate.clamp(0.25, 4.0);
+    if (rate - 1.0).abs() < 0.01 || samples.len() < FRAME * 2 {
+        return samples.to_vec();
+    }
+    // a hann window at half overlap sums to 1
+    let window = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME as f32).cos())
+        .collect::<Vec<_>>();
+    let last = samples.len() - FRAME;
+    let out_len = (samples.len() as f32 / rate) as usize;
+    let mut out = vec![0f32; out_len + FRAME];
+
+    let mut prev: Option<usize> = None;
+    for out_pos in (0..out_len).step_by(HOP) {
+        let nominal = ((out_pos as f32 * rate) as usize).min(last);
+        let pos = match prev {
+            // the frame most like the natural continuation of the previous one
+            Some(prev) if prev + HOP <= last => {
+                let target = &samples[prev + HOP..prev + HOP + HOP];
+                let corr = |p: usize| -> f32 {
+                    samples[p..p + HOP]
+                        .iter()
+                        .zip(target)
+                        .map(|(x, y)| x * y)
+                        .sum()
+                };
+                (nominal.saturating_sub(TOLERANCE)..=(nominal + TOLERANCE).min(last))
+                    .map(|p| (p, corr(p)))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map_or(nominal, |(p, _)| p)
+            }
+            _ => nominal,
+        };
+        for (i, w) in window.iter().enumerate() {
+            out[out_pos + i] += samples[pos + i] * w;
+        }
+        prev = Some(pos);
+    }
+    out.truncate(out_len);
+    out
+}
+
+/// Scale the volume, the samples are clipped to [-1, 1]
+pub fn gain(samples: &mut [f32], volume: f32) {
+    if volume == 1.0 {
+        return;
+    }
+    samples
+        .iter_mut()
+        .for_each(|s| *s = (*s * volume).clamp(-1.0, 1.0));
+}
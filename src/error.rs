@@ -9,6 +9,8 @@ pub enum NihilityGsvError {
     #[error(transparent)]
     Pest(#[from] Box<pest::error::Error<crate::text::num::Rule>>),
     #[error(transparent)]
+    Jieba(#[from] jieba_rs::Error),
+    #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     WavRead(#[from] wav_io::reader::DecodeError),
@@ -18,6 +20,12 @@ pub enum NihilityGsvError {
     FeatureExtraction(String),
     #[error("Infer Error: {0}")]
     Infer(String),
+    #[error("Dict Error: {0}")]
+    Dict(String),
+    #[error("Rule Error: {0}")]
+    Rule(String),
+    #[error("Ssml Error: {0}")]
+    Ssml(String),
 }
 
 impl actix_web::error::ResponseError for NihilityGsvError {}
@@ -1,3 +1,4 @@
+pub mod audio;
 pub mod error;
 pub mod gsv;
 pub mod ssl;
@@ -7,14 +8,22 @@ pub mod text;
 use crate::error::*;
 use crate::gsv::Gsv;
 use crate::ssl::SSL;
-use crate::text::{G2PConfig, G2p};
+use crate::text::dict::DictConfig;
+use crate::text::normalize::NormalizeConfig;
+use crate::text::rewrite::{RewriteRule, Rewriter};
+use crate::text::ssml::Part;
+use crate::text::{
+    ErrorPolicy, G2PConfig, G2p, JiebaConfig, Lang, PhoneSegment, TextLanguage, TextOptions,
+    TextReport,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fs;
-use std::path::{Path};
+use std::path::Path;
 pub use tch;
 use tch::Device;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use wav_io::header::WavHeader;
 
 const REF_PATH: &str = "ref.wav";
@@ -29,13 +38,57 @@ pub struct NihilityGsvConfig {
     pub ssl_model: String,
     pub gsv_dir: String,
     pub selected_model: String,
+    /// japanese g2p, only works when built with the `enable_jp` feature
+    #[serde(default)]
+    pub enable_jp: bool,
+    /// korean g2p, only works when built with the `enable_ko` feature
+    #[serde(default)]
+    pub enable_ko: bool,
+    /// cantonese g2p, only works when built with the `enable_yue` feature
+    #[serde(default)]
+    pub enable_yue: bool,
+    /// unicode normalization before segmentation
+    #[serde(default)]
+    pub normalize: NormalizeConfig,
+    /// user dictionaries over the bundled ones
+    #[serde(default)]
+    pub dict: DictConfig,
+    /// chinese segmentation, a user dict and the hmm
+    #[serde(default)]
+    pub jieba: JiebaConfig,
+    /// json file of chinese polyphone rules, see `text::polyphone`
+    #[serde(default)]
+    pub polyphone_rules: Option<String>,
+    /// other models in `gsv_dir` to load, an ssml `voice` element picks one of them
+    #[serde(default)]
+    pub voices: Vec<String>,
+    /// unknown phones and text that can not be read, a request may override it
+    #[serde(default)]
+    pub error_policy: ErrorPolicy,
+    /// regex rewrites of the request text, in order, see `text::rewrite`
+    #[serde(default)]
+    pub rewrite: Vec<RewriteRule>,
 }
 
 #[derive(Clone)]
 pub struct NihilityGsv {
     g2p: G2p,
     gsv: Gsv,
+    /// the loaded voices by name, with the selected model
+    voices: HashMap<String, Gsv>,
     wav_header: WavHeader,
+    error_policy: ErrorPolicy,
+    selected_model: String,
+    rewriter: Rewriter,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    #[default]
+    Plain,
+    /// a subset of SSML, see `text::ssml`
+    Ssml,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +96,15 @@ pub struct NihilityGsv {
 pub struct NihilityGsvInferParam {
     pub text: String,
     pub top_k: i64,
+    /// language of the text, detected per word when auto
+    pub language: TextLanguage,
+    /// read numbers in this language, follow the surrounding text when not set
+    pub num_lang: Option<Lang>,
+    /// phones to read instead of `text`
+    pub segments: Option<Vec<PhoneSegment>>,
+    pub text_format: TextFormat,
+    /// the configured policy when not set
+    pub error_policy: Option<ErrorPolicy>,
 }
 
 impl NihilityGsvConfig {
@@ -58,7 +120,59 @@ impl NihilityGsvConfig {
                 "GSV base model does not exist".into(),
             ));
         }
-        let gsv_base_dir = Path::new(&self.gsv_dir).join(&self.selected_model);
+        text::dict::load(&self.dict)?;
+        let rewriter = Rewriter::new(&self.rewrite)?;
+        let g2p_conf = G2PConfig::new(self.g2p_en_model.clone())
+            .with_chinese(self.g2p_zh_model.clone(), self.bert_model.clone())
+            .with_normalize(self.normalize.clone())
+            .with_jieba(self.jieba.clone())
+            .with_polyphone_rules(self.polyphone_rules.clone());
+        #[cfg(feature = "enable_jp")]
+        let g2p_conf = g2p_conf.with_jp(self.enable_jp);
+        #[cfg(not(feature = "enable_jp"))]
+        if self.enable_jp {
+            tracing::warn!("enable_jp is set, but the enable_jp feature is not built in");
+        }
+        #[cfg(feature = "enable_ko")]
+        let g2p_conf = g2p_conf.with_ko(self.enable_ko);
+        #[cfg(not(feature = "enable_ko"))]
+        if self.enable_ko {
+            tracing::warn!("enable_ko is set, but the enable_ko feature is not built in");
+        }
+        #[cfg(feature = "enable_yue")]
+        let g2p_conf = g2p_conf.with_yue(self.enable_yue);
+        #[cfg(not(feature = "enable_yue"))]
+        if self.enable_yue {
+            tracing::warn!("enable_yue is set, but the enable_yue feature is not built in");
+        }
+        let g2p = g2p_conf.build(device)?;
+
+        let _g = tch::no_grad_guard();
+
+        let wav_header = wav_io::new_header(32000, 16, false, true);
+        let ssl = SSL::new(&self.ssl_model, device)?;
+
+        let gsv = self.load_voice(&self.selected_model, &g2p, &ssl, device)?;
+        let mut voices = HashMap::from([(self.selected_model.clone(), gsv.clone())]);
+        for name in &self.voices {
+            if !voices.contains_key(name) {
+                voices.insert(name.clone(), self.load_voice(name, &g2p, &ssl, device)?);
+            }
+        }
+        Ok(NihilityGsv {
+            g2p,
+            gsv,
+            voices,
+            wav_header,
+            error_policy: self.error_policy,
+            selected_model: self.selected_model.clone(),
+            rewriter,
+        })
+    }
+
+    /// the model, ref audio and ref text in the `name` dir of `gsv_dir`
+    fn load_voice(&self, name: &str, g2p: &G2p, ssl: &SSL, device: Device) -> Result<Gsv> {
+        let gsv_base_dir = Path::new(&self.gsv_dir).join(name);
         if !gsv_base_dir.exists() {
             error!("GSV model dir does not exist");
             return Err(NihilityGsvError::Infer(
@@ -93,9 +207,6 @@ impl NihilityGsvConfig {
             .expect("Gsv model path exception")
             .to_string();
 
-        let g2p_conf = G2PConfig::new(self.g2p_en_model.clone())
-            .with_chinese(self.g2p_zh_model.clone(), self.bert_model.clone());
-        let g2p = g2p_conf.build(device)?;
         let file = fs::File::open(ref_path)?;
         let (head, mut ref_audio_samples) = wav_io::read_from_file(file)?;
         info!("ref wav file head: {:?}", head);
@@ -104,60 +215,184 @@ impl NihilityGsvConfig {
             ref_audio_samples =
                 wav_io::resample::linear(ref_audio_samples, 1, head.sample_rate, 32000);
         }
-        let (ref_seq, ref_bert) = text::get_phone_and_bert(&g2p, &ref_text)?;
+        let options = TextOptions {
+            policy: self.error_policy,
+            ..Default::default()
+        };
+        let (ref_seq, ref_bert) = text::get_phone_and_bert(g2p, &ref_text, &options)?;
         let ref_audio_32k = tch::Tensor::from_slice(&ref_audio_samples)
             .to_device(device)
             .unsqueeze(0);
-        info!("load ref done");
-
-        let _g = tch::no_grad_guard();
-
-        let wav_header = wav_io::new_header(32000, 16, false, true);
-        let ssl = SSL::new(&self.ssl_model, device)?;
+        info!("load ref of {} done", name);
 
         let ref_audio_16k = ssl.resample(&ref_audio_32k, 32000, 16000)?;
         let mut ssl_content = ssl.to_ssl_content(&ref_audio_16k)?;
         if ref_audio_32k.kind() == tch::Kind::Half {
             ssl_content = ssl_content.internal_cast_half(false);
         }
-        let gsv = Gsv::new(
+        Gsv::new(
             &gsv_model_path,
             device,
             ssl_content,
             ref_audio_32k,
             ref_seq,
             ref_bert,
-        )?;
-        Ok(NihilityGsv {
-            g2p,
-            gsv,
-            wav_header,
-        })
+        )
     }
 }
 
 impl NihilityGsv {
     pub fn infer(&self, param: NihilityGsvInferParam) -> Result<Vec<f32>> {
-        info!("infer text: {}", param.text);
+        Ok(self.infer_with_report(param)?.0)
+    }
+
+    /// Infer and return what the text frontend skipped or replaced
+    pub fn infer_with_report(
+        &self,
+        param: NihilityGsvInferParam,
+    ) -> Result<(Vec<f32>, TextReport)> {
+        let mut report = TextReport::default();
+        let options = TextOptions {
+            language: param.language,
+            num_lang: param.num_lang,
+            policy: param.error_policy.unwrap_or(self.error_policy),
+        };
+        if param.text_format == TextFormat::Ssml && param.segments.is_none() {
+            let samples = self.infer_ssml(&param, &options, &mut report)?;
+            return Ok((samples, report));
+        }
         let st = std::time::Instant::now();
         let _g = tch::no_grad_guard();
-        let (text_seq, text_bert) = text::get_phone_and_bert(&self.g2p, &param.text)?;
+        let (text_seq, text_bert) = match &param.segments {
+            Some(segments) => {
+                info!("infer {} phone segments", segments.len());
+                text::get_segments_phone_and_bert(&self.g2p, segments)?
+            }
+            None => {
+                info!("infer text: {}", param.text);
+                text::dict::reload_if_changed();
+                let text = self.rewrite(&param.text, param.language, None);
+                if text != param.text {
+                    info!("rewritten text: {}", text);
+                }
+                report.text.push(text.clone());
+                text::get_parts_phone_and_bert(
+                    &self.g2p,
+                    &[(&text, param.language)],
+                    &options,
+                    &mut report,
+                )?
+            }
+        };
         let audio = self.gsv.infer(&text_seq, &text_bert, param.top_k)?;
         info!("infer done, cost: {:?}", st.elapsed());
-        let audio_size = audio.size1().expect("Failed to get audio size") as usize;
-        let mut samples = vec![0f32; audio_size];
-        audio.f_copy_data(&mut samples, audio_size)?;
+        Ok((to_samples(&audio)?, report))
+    }
+
+    /// Read the `text` of `param` as SSML, the voice and prosody of a part may differ
+    fn infer_ssml(
+        &self,
+        param: &NihilityGsvInferParam,
+        options: &TextOptions,
+        report: &mut TextReport,
+    ) -> Result<Vec<f32>> {
+        info!("infer ssml: {}", param.text);
+        let st = std::time::Instant::now();
+        let _g = tch::no_grad_guard();
+        text::dict::reload_if_changed();
+        let parts = text::ssml::parse(&param.text)?;
+        let mut samples = vec![];
+        // texts of one voice and prosody are read as one sentence, even in other languages
+        let chunks = parts.chunk_by(|a, b| match (a, b) {
+            (Part::Text(_, a), Part::Text(_, b)) => a.voice == b.voice && a.prosody == b.prosody,
+            _ => false,
+        });
+        for chunk in chunks {
+            let (texts, style) = match chunk {
+                [Part::Break(seconds), ..] => {
+                    samples.extend(std::iter::repeat_n(0f32, (seconds * 32000.0) as usize));
+                    continue;
+                }
+                [Part::Text(_, style), ..] => (chunk, style),
+                [] => continue,
+            };
+            let texts = texts
+                .iter()
+                .filter_map(|p| match p {
+                    Part::Text(t, s) => {
+                        let language = s.language.unwrap_or(param.language);
+                        Some((self.rewrite(t, language, s.voice.as_deref()), language))
+                    }
+                    Part::Break(_) => None,
+                })
+                .collect::<Vec<_>>();
+            report.text.extend(texts.iter().map(|(t, _)| t.clone()));
+            let texts = texts
+                .iter()
+                .map(|(t, language)| (t.as_str(), *language))
+                .collect::<Vec<_>>();
+            let (text_seq, text_bert) =
+                text::get_parts_phone_and_bert(&self.g2p, &texts, options, report)?;
+            let audio =
+                self.voice(style.voice.as_deref())
+                    .infer(&text_seq, &text_bert, param.top_k)?;
+            let mut audio = audio::time_stretch(&to_samples(&audio)?, style.prosody.rate);
+            audio::gain(&mut audio, style.prosody.volume);
+            samples.extend(audio);
+        }
+        info!("infer done, cost: {:?}", st.elapsed());
         Ok(samples)
     }
 
+    /// The text after the rewrite rules for `language` and `voice`, the selected one when not set
+    pub fn rewrite(&self, text: &str, language: TextLanguage, voice: Option<&str>) -> String {
+        let voice = voice
+            .filter(|v| self.voices.contains_key(*v))
+            .unwrap_or(self.selected_model.as_str());
+        self.rewriter.apply(text, language, voice).into_owned()
+    }
+
+    /// a loaded voice, or the selected one
+    fn voice(&self, name: Option<&str>) -> &Gsv {
+        let Some(name) = name else {
+            return &self.gsv;
+        };
+        self.voices.get(name).unwrap_or_else(|| {
+            warn!("voice {} is not loaded, use the selected one", name);
+            &self.gsv
+        })
+    }
+
+    /// Reload the user dictionaries from the configured files
+    pub fn reload_dict(&self) -> Result<()> {
+        text::dict::reload()
+    }
+
+    /// Add a word to the chinese segmentation, so names are not split apart
+    pub fn add_word(&mut self, word: &str, freq: Option<usize>, tag: Option<&str>) -> usize {
+        self.g2p.add_word(word, freq, tag)
+    }
+
     pub fn infer_out_to_wav(&self, param: NihilityGsvInferParam) -> Result<Vec<u8>> {
-        Ok(wav_io::write_to_bytes(
-            &self.wav_header,
-            &self.infer(param)?,
-        )?)
+        Ok(self.infer_out_to_wav_with_report(param)?.0)
+    }
+
+    pub fn infer_out_to_wav_with_report(
+        &self,
+        param: NihilityGsvInferParam,
+    ) -> Result<(Vec<u8>, TextReport)> {
+        let (samples, report) = self.infer_with_report(param)?;
+        Ok((wav_io::write_to_bytes(&self.wav_header, &samples)?, report))
     }
 }
 
+fn to_samples(audio: &tch::Tensor) -> Result<Vec<f32>> {
+    let audio_size = audio.size1()? as usize;
+    let mut samples = vec![0f32; audio_size];
+    audio.f_copy_data(&mut samples, audio_size)?;
+    Ok(samples)
+}
+
 impl Default for NihilityGsvConfig {
     fn default() -> Self {
         NihilityGsvConfig {
@@ -167,6 +402,16 @@ impl Default for NihilityGsvConfig {
             ssl_model: "base/ssl.pt".to_string(),
             gsv_dir: "model".to_string(),
             selected_model: "default".to_string(),
+            enable_jp: false,
+            enable_ko: false,
+            enable_yue: false,
+            normalize: NormalizeConfig::default(),
+            dict: DictConfig::default(),
+            jieba: JiebaConfig::default(),
+            polyphone_rules: None,
+            voices: vec![],
+            error_policy: ErrorPolicy::default(),
+            rewrite: vec![],
         }
     }
 }
@@ -176,6 +421,11 @@ impl Default for NihilityGsvInferParam {
         NihilityGsvInferParam {
             text: "".to_string(),
             top_k: 15,
+            language: TextLanguage::Auto,
+            num_lang: None,
+            segments: None,
+            text_format: TextFormat::Plain,
+            error_policy: None,
         }
     }
 }
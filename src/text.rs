@@ -1,7 +1,7 @@
 use crate::error::*;
 use std::{
     borrow::Cow,
-    collections::{HashMap, LinkedList},
+    collections::{HashMap, HashSet, LinkedList},
     fmt::Debug,
     str::FromStr,
     sync::Arc,
@@ -9,23 +9,86 @@ use std::{
 
 use pest::Parser;
 use tch::{Kind, Tensor};
-use tokenizers::Tokenizer;
+use tokenizers::{Encoding, Tokenizer};
 use tracing::{debug, info, trace, warn};
 
 pub mod g2p_en;
 #[cfg(feature = "enable_jp")]
 pub mod g2p_jp;
+#[cfg(feature = "enable_ko")]
+pub mod g2p_ko;
+#[cfg(feature = "enable_yue")]
+pub mod g2p_yue;
 pub mod g2pw;
 
+pub mod acronym;
 pub mod dict;
+pub mod markup;
+pub mod normalize;
 pub mod num;
+pub mod polyphone;
+pub mod rewrite;
+pub mod ssml;
 
 const SEPARATOR: &str = " ";
+/// tokens in one bert forward, with '[CLS]' and '[SEP]' it is the 512 token limit
+const BERT_MAX_TOKENS: usize = 510;
+/// context on each side of a bert window
+const BERT_OVERLAP: usize = 32;
+
+/// Chinese segmentation, a user dict has `word [freq] [tag]` lines like the python jieba
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct JiebaConfig {
+    pub dict: Option<String>,
+    /// hmm finds words that are not in the dict, but may also split names apart
+    pub hmm: bool,
+}
+
+impl Default for JiebaConfig {
+    fn default() -> Self {
+        Self {
+            dict: None,
+            hmm: true,
+        }
+    }
+}
+
+/// unlike `Jieba::load_dict` a missing freq is suggested, so the word is still kept together
+fn load_jieba_dict(jieba: &mut jieba_rs::Jieba, path: &str) -> Result<()> {
+    let dict = std::fs::read_to_string(path)?;
+    for (n, line) in dict.lines().enumerate() {
+        let mut iter = line.split_whitespace();
+        let Some(word) = iter.next() else {
+            continue;
+        };
+        let (freq, tag) = match (iter.next(), iter.next()) {
+            (Some(freq), tag) if freq.starts_with(|c: char| c.is_ascii_digit()) => {
+                let freq = freq.parse::<usize>().map_err(|e| {
+                    jieba_rs::Error::InvalidDictEntry(format!(
+                        "{path} line {}: frequency {freq}: {e}",
+                        n + 1
+                    ))
+                })?;
+                (Some(freq), tag)
+            }
+            (tag, _) => (None, tag),
+        };
+        jieba.add_word(word, freq, tag);
+    }
+    Ok(())
+}
 
 pub struct G2PConfig {
     pub cn_setting: Option<(String, String)>,
     pub g2p_en_path: String,
     pub enable_jp: bool,
+    pub enable_ko: bool,
+    pub enable_yue: bool,
+    pub normalize: normalize::NormalizeConfig,
+    /// json file of chinese polyphone rules
+    pub polyphone_rules: Option<String>,
+    pub jieba: JiebaConfig,
 }
 
 impl G2PConfig {
@@ -34,6 +97,11 @@ impl G2PConfig {
             cn_setting: None,
             g2p_en_path,
             enable_jp: false,
+            enable_ko: false,
+            enable_yue: false,
+            normalize: normalize::NormalizeConfig::default(),
+            polyphone_rules: None,
+            jieba: JiebaConfig::default(),
         }
     }
 
@@ -42,11 +110,36 @@ impl G2PConfig {
         self
     }
 
+    pub fn with_normalize(self, normalize: normalize::NormalizeConfig) -> Self {
+        Self { normalize, ..self }
+    }
+
+    pub fn with_jieba(self, jieba: JiebaConfig) -> Self {
+        Self { jieba, ..self }
+    }
+
+    pub fn with_polyphone_rules(self, polyphone_rules: Option<String>) -> Self {
+        Self {
+            polyphone_rules,
+            ..self
+        }
+    }
+
     #[cfg(feature = "enable_jp")]
     pub fn with_jp(self, enable_jp: bool) -> Self {
         Self { enable_jp, ..self }
     }
 
+    #[cfg(feature = "enable_ko")]
+    pub fn with_ko(self, enable_ko: bool) -> Self {
+        Self { enable_ko, ..self }
+    }
+
+    #[cfg(feature = "enable_yue")]
+    pub fn with_yue(self, enable_yue: bool) -> Self {
+        Self { enable_yue, ..self }
+    }
+
     pub fn build(&self, device: tch::Device) -> Result<G2p> {
         let (cn_bert, g2pw) = match &self.cn_setting {
             Some((g2pw_path, cn_bert_path)) => {
@@ -64,6 +157,14 @@ impl G2PConfig {
             }
             _ => (CNBertModel::default(), g2pw::G2PWConverter::empty()),
         };
+        let mut jieba = jieba_rs::Jieba::new();
+        if let Some(path) = &self.jieba.dict {
+            load_jieba_dict(&mut jieba, path)?;
+        }
+        let polyphone = match &self.polyphone_rules {
+            Some(path) => polyphone::PolyphoneRules::load(path)?,
+            None => polyphone::PolyphoneRules::default(),
+        };
 
         Ok(G2p {
             zh_bert: cn_bert,
@@ -73,8 +174,14 @@ impl G2PConfig {
             g2p_jp: g2p_jp::G2PJpConverter::new(),
             device,
             symbols: crate::symbols::SYMBOLS.clone(),
-            jieba: jieba_rs::Jieba::new(),
+            symbol_ids: crate::symbols::SYMBOLS.values().copied().collect(),
+            jieba,
+            jieba_hmm: self.jieba.hmm,
             enable_jp: self.enable_jp,
+            enable_ko: self.enable_ko,
+            enable_yue: self.enable_yue,
+            normalize: self.normalize.clone(),
+            polyphone: Arc::new(polyphone),
         })
     }
 }
@@ -88,8 +195,15 @@ pub struct G2p {
     g2p_jp: g2p_jp::G2PJpConverter,
     pub device: tch::Device,
     symbols: HashMap<String, i64>,
+    /// the ids of `symbols`, for phone segments that give ids
+    symbol_ids: HashSet<i64>,
     jieba: jieba_rs::Jieba,
+    jieba_hmm: bool,
     enable_jp: bool,
+    enable_ko: bool,
+    enable_yue: bool,
+    normalize: normalize::NormalizeConfig,
+    polyphone: Arc<polyphone::PolyphoneRules>,
 }
 
 impl G2p {
@@ -101,7 +215,12 @@ impl G2p {
         device: tch::Device,
         symbols: HashMap<String, i64>,
         jieba: jieba_rs::Jieba,
+        jieba_hmm: bool,
         enable_jp: bool,
+        enable_ko: bool,
+        enable_yue: bool,
+        normalize: normalize::NormalizeConfig,
+        polyphone: Arc<polyphone::PolyphoneRules>,
     ) -> Self {
         Self {
             zh_bert,
@@ -110,17 +229,76 @@ impl G2p {
             #[cfg(feature = "enable_jp")]
             g2p_jp,
             device,
+            symbol_ids: symbols.values().copied().collect(),
             symbols,
             jieba,
+            jieba_hmm,
             enable_jp,
+            enable_ko,
+            enable_yue,
+            normalize,
+            polyphone,
         }
     }
+
+    /// Add a word to jieba at runtime, the freq is suggested when not set
+    pub fn add_word(&mut self, word: &str, freq: Option<usize>, tag: Option<&str>) -> usize {
+        self.jieba.add_word(word, freq, tag)
+    }
 }
 
-#[inline]
-fn get_phone_symbol(symbols: &HashMap<String, i64>, ph: &str) -> i64 {
-    // symbols[','] : 3
-    symbols.get(ph).copied().unwrap_or(3)
+/// How the text frontend handles a phone that is not a symbol and text it can not read, the
+/// same in debug and release builds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorPolicy {
+    /// fail with the phone or text
+    Strict,
+    /// skip it and report a warning
+    #[default]
+    Lenient,
+    /// spell unknown words and read unknown phones as a pause, with a warning
+    Replace,
+}
+
+/// What the text frontend skipped or replaced under the `ErrorPolicy`
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TextReport {
+    pub warnings: Vec<String>,
+    /// the text as read, after the rewrite rules
+    pub text: Vec<String>,
+}
+
+impl TextReport {
+    fn warn(&mut self, warning: String) {
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+}
+
+const UNKNOWN_PHONE: i64 = -1;
+
+/// Phone ids of one request, the unknown phones are kept for the `ErrorPolicy`
+struct PhoneLookup<'a> {
+    symbols: &'a HashMap<String, i64>,
+    policy: ErrorPolicy,
+    unknown: Vec<String>,
+}
+
+impl PhoneLookup<'_> {
+    fn id(&mut self, ph: &str) -> i64 {
+        match self.symbols.get(ph) {
+            Some(id) => *id,
+            None => {
+                self.unknown.push(ph.to_string());
+                match self.policy {
+                    // symbols[','] : 3
+                    ErrorPolicy::Replace => 3,
+                    _ => UNKNOWN_PHONE,
+                }
+            }
+        }
+    }
 }
 
 fn split_zh_ph(ph: &str) -> (&str, &str) {
@@ -500,8 +678,11 @@ pub fn split_text(text: &str, max_chunk_size: usize) -> Vec<&str> {
             // skip number like "1.2"
             // skip expression like "1,000"
             // skip email like "example@gmail.com"
+            // skip url query like "?id=1"
             let mut t0 = t.0.chars();
-            if t0.next_back().is_some_and(|c| c == '.' || c == ',')
+            if t0
+                .next_back()
+                .is_some_and(|c| c == '.' || c == ',' || c == '?')
                 && t.1.chars().next().is_some_and(|c| c != ' ' && c != '\n')
             {
                 total_count += count;
@@ -538,24 +719,85 @@ pub fn split_text(text: &str, max_chunk_size: usize) -> Vec<&str> {
 }
 
 /// return: (phone_seq, bert_seq)
-pub fn get_phone_and_bert(g2p: &G2p, text: &str) -> Result<(Tensor, Tensor)> {
+pub fn get_phone_and_bert(
+    g2p: &G2p,
+    text: &str,
+    options: &TextOptions,
+) -> Result<(Tensor, Tensor)> {
+    get_parts_phone_and_bert(
+        g2p,
+        &[(text, options.language)],
+        options,
+        &mut TextReport::default(),
+    )
+}
+
+/// return: (phone_seq, bert_seq) of texts in their own language, read as one sentence. The
+/// language of a part replaces `options.language`.
+pub fn get_parts_phone_and_bert(
+    g2p: &G2p,
+    parts: &[(&str, TextLanguage)],
+    options: &TextOptions,
+    report: &mut TextReport,
+) -> Result<(Tensor, Tensor)> {
     let mut phone_seq = Vec::new();
     let mut bert_seq = Vec::new();
 
-    let mut phone_builder = PhoneBuilder::new(g2p.enable_jp);
-    phone_builder.push_text(&g2p.jieba, text);
+    let mut phone_builder = PhoneBuilder::new(g2p.enable_jp)
+        .with_ko(g2p.enable_ko)
+        .with_yue(g2p.enable_yue)
+        .with_num_lang(options.num_lang)
+        .with_hmm(g2p.jieba_hmm)
+        .with_policy(options.policy);
+    for (text, language) in parts {
+        let enabled = match language {
+            l if l.need_jp() => g2p.enable_jp,
+            l if l.need_ko() => g2p.enable_ko,
+            l if l.need_yue() => g2p.enable_yue,
+            _ => true,
+        };
+        if !enabled {
+            return Err(NihilityGsvError::Infer(format!(
+                "text language {:?} is not enabled",
+                language
+            )));
+        }
+        phone_builder = phone_builder.with_language(*language);
+        phone_builder.push_text(
+            &g2p.jieba,
+            &normalize::unicode::normalize(text, &g2p.normalize),
+        );
+    }
+    let text = parts.iter().map(|(t, _)| *t).collect::<String>();
     if !text.ends_with(['。', '.', '?', '？', '!', '！']) {
         phone_builder.push_punctuation(".");
     }
+    let unread = std::mem::take(&mut phone_builder.unread);
+    match options.policy {
+        ErrorPolicy::Strict if !unread.is_empty() => {
+            return Err(NihilityGsvError::Infer(format!(
+                "can not read {:?} in {}",
+                unread, text
+            )));
+        }
+        ErrorPolicy::Replace => unread
+            .into_iter()
+            .for_each(|t| report.warn(format!("read {:?} as letters or a pause", t))),
+        _ => unread
+            .into_iter()
+            .for_each(|t| report.warn(format!("skip {:?}", t))),
+    }
 
     fn helper<I: IntoIterator<Item = Sentence>>(
         i: I,
         g2p: &G2p,
+        lookup: &mut PhoneLookup,
+        report: &mut TextReport,
         phone_seq: &mut Vec<Tensor>,
         bert_seq: &mut Vec<Tensor>,
     ) -> Result<()> {
         for s in i {
-            match s {
+            let (text, built) = match s {
                 Sentence::Zh(mut zh) => {
                     trace!("zh text: {:?}", zh.zh_text);
                     trace!("zh phones: {:?}", zh.phones);
@@ -564,66 +806,91 @@ pub fn get_phone_and_bert(g2p: &G2p, text: &str) -> Result<(Tensor, Tensor)> {
                         continue;
                     }
 
-                    zh.generate_pinyin(g2p);
-                    match zh.build_phone_and_bert(g2p) {
-                        Ok((t, bert)) => {
-                            phone_seq.push(t);
-                            bert_seq.push(bert);
-                        }
-                        Err(e) => {
-                            if cfg!(debug_assertions) {
-                                return Err(e);
-                            } else {
-                                warn!("get a error, skip: {}", zh.zh_text);
-                                warn!("zh build_phone_and_bert error: {}", e);
-                            }
-                        }
-                    };
+                    zh.generate_pinyin(g2p, lookup);
+                    (zh.zh_text.clone(), zh.build_phone_and_bert(g2p))
                 }
                 Sentence::En(mut en) => {
                     trace!("en text: {:?}", en.en_text);
                     trace!("en phones: {:?}", en.phones);
-                    en.generate_phones(g2p);
-                    match en.build_phone_and_bert(g2p) {
-                        Ok((t, bert)) => {
-                            phone_seq.push(t);
-                            bert_seq.push(bert);
-                        }
-                        Err(e) => {
-                            if cfg!(debug_assertions) {
-                                return Err(e);
-                            } else {
-                                warn!("get a error, skip: {:?}", en.en_text);
-                                warn!("zh build_phone_and_bert error: {}", e);
-                            }
-                        }
-                    };
+                    en.generate_phones(g2p, lookup);
+                    (format!("{:?}", en.en_text), en.build_phone_and_bert(g2p))
                 }
                 #[cfg(feature = "enable_jp")]
                 Sentence::Jp(jp) => {
                     trace!("jp text: {:?}", jp.text);
-                    match jp.build_phone_and_bert(g2p) {
-                        Ok((t, bert)) => {
-                            phone_seq.push(t);
-                            bert_seq.push(bert);
-                        }
-                        Err(e) => {
-                            if cfg!(debug_assertions) {
-                                return Err(e);
-                            } else {
-                                warn!("get a error, skip: {:?}", jp.text);
-                                warn!("jp build_phone_and_bert error: {}", e);
-                            }
-                        }
+                    (jp.text.clone(), jp.build_phone_and_bert(g2p, lookup))
+                }
+                #[cfg(feature = "enable_ko")]
+                Sentence::Ko(ko) => {
+                    trace!("ko text: {:?}", ko.text);
+                    (ko.text.clone(), ko.build_phone_and_bert(g2p, lookup))
+                }
+                #[cfg(feature = "enable_yue")]
+                Sentence::Yue(yue) => {
+                    trace!("yue text: {:?}", yue.text);
+                    (yue.text.clone(), yue.build_phone_and_bert(g2p, lookup))
+                }
+                Sentence::Num(num) => {
+                    helper(
+                        num.to_phone_sentence()?,
+                        g2p,
+                        lookup,
+                        report,
+                        phone_seq,
+                        bert_seq,
+                    )?;
+                    continue;
+                }
+            };
+            let unknown = std::mem::take(&mut lookup.unknown);
+            let (mut t, mut bert) = match built {
+                Ok(v) => v,
+                Err(e) if lookup.policy == ErrorPolicy::Strict => return Err(e),
+                Err(e) => {
+                    report.warn(format!("skip {}: {}", text, e));
+                    continue;
+                }
+            };
+            if !unknown.is_empty() {
+                match lookup.policy {
+                    ErrorPolicy::Strict => {
+                        return Err(NihilityGsvError::Infer(format!(
+                            "unknown phones {:?} in {}",
+                            unknown, text
+                        )));
+                    }
+                    ErrorPolicy::Lenient => {
+                        report.warn(format!("skip unknown phones {:?} in {}", unknown, text));
+                        // a bert row belongs to each phone
+                        let keep = t.ne(UNKNOWN_PHONE).squeeze_dim(0).nonzero().squeeze_dim(1);
+                        t = t.index_select(1, &keep);
+                        bert = bert.index_select(0, &keep);
                     }
+                    ErrorPolicy::Replace => report.warn(format!(
+                        "read unknown phones {:?} in {} as a pause",
+                        unknown, text
+                    )),
                 }
-                Sentence::Num(num) => helper(num.to_phone_sentence()?, g2p, phone_seq, bert_seq)?,
             }
+            phone_seq.push(t);
+            bert_seq.push(bert);
         }
         Ok(())
     }
 
-    helper(phone_builder.sentence, g2p, &mut phone_seq, &mut bert_seq)?;
+    let mut lookup = PhoneLookup {
+        symbols: &g2p.symbols,
+        policy: options.policy,
+        unknown: vec![],
+    };
+    helper(
+        phone_builder.sentence,
+        g2p,
+        &mut lookup,
+        report,
+        &mut phone_seq,
+        &mut bert_seq,
+    )?;
 
     if phone_seq.is_empty() {
         return Err(NihilityGsvError::FeatureExtraction(format!(
@@ -648,6 +915,96 @@ pub fn get_phone_and_bert(g2p: &G2p, text: &str) -> Result<(Tensor, Tensor)> {
     Ok((phone_seq, bert_seq))
 }
 
+/// A phone by its symbol name like "n", "i2" or "AH0", or by its id
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Phone {
+    Id(i64),
+    Symbol(String),
+}
+
+/// Phones given by the caller instead of text, they skip the text frontend
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PhoneSegment {
+    pub phones: Vec<Phone>,
+    /// only chinese segments get bert features
+    #[serde(default)]
+    pub lang: Option<Lang>,
+    /// the text of a chinese segment for the bert features, a chinese char has 2 phones and
+    /// any other char 1
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+impl PhoneSegment {
+    fn phone_ids(&self, g2p: &G2p) -> Result<Vec<i64>> {
+        self.phones
+            .iter()
+            .map(|p| {
+                match p {
+                    Phone::Id(id) => g2p.symbol_ids.get(id),
+                    Phone::Symbol(s) => g2p.symbols.get(s),
+                }
+                .copied()
+                .ok_or_else(|| NihilityGsvError::Infer(format!("unknown phone: {:?}", p)))
+            })
+            .collect()
+    }
+
+    fn build_phone_and_bert(&self, g2p: &G2p) -> Result<(Tensor, Tensor)> {
+        let phones_ids = self.phone_ids(g2p)?;
+        let bert = match (&self.text, self.lang) {
+            (Some(text), Some(Lang::Zh)) => {
+                let word2ph = text
+                    .chars()
+                    .map(|c| if is_han(c) { 2 } else { 1 })
+                    .collect::<Vec<i32>>();
+                let len: i32 = word2ph.iter().sum();
+                if len as usize != phones_ids.len() {
+                    return Err(NihilityGsvError::Infer(format!(
+                        "segment text {:?} needs {} phones, got {}",
+                        text,
+                        len,
+                        phones_ids.len()
+                    )));
+                }
+                g2p.zh_bert.get_text_bert(text, &word2ph, g2p.device)?
+            }
+            _ => Tensor::zeros([phones_ids.len() as i64, 1024], (Kind::Float, g2p.device)),
+        };
+        let t = Tensor::from_slice(&phones_ids)
+            .to_device(g2p.device)
+            .unsqueeze(0);
+
+        Ok((t, bert))
+    }
+}
+
+/// return: (phone_seq, bert_seq) of the segments, like `get_phone_and_bert`
+pub fn get_segments_phone_and_bert(
+    g2p: &G2p,
+    segments: &[PhoneSegment],
+) -> Result<(Tensor, Tensor)> {
+    let mut phone_seq = Vec::new();
+    let mut bert_seq = Vec::new();
+    for segment in segments.iter().filter(|s| !s.phones.is_empty()) {
+        let (t, bert) = segment.build_phone_and_bert(g2p)?;
+        phone_seq.push(t);
+        bert_seq.push(bert);
+    }
+    if phone_seq.is_empty() {
+        return Err(NihilityGsvError::FeatureExtraction(
+            "segments get phone_seq is empty".into(),
+        ));
+    }
+
+    let phone_seq = Tensor::cat(&phone_seq, 1).to(g2p.device);
+    let bert_seq = Tensor::cat(&bert_seq, 0).to(g2p.device);
+    debug!("segments bert_seq: {:?}", bert_seq);
+
+    Ok((phone_seq, bert_seq))
+}
+
 #[derive(Debug, Clone)]
 pub enum CNBertModel {
     None,
@@ -672,36 +1029,26 @@ impl CNBertModel {
         }
     }
 
-    fn encode_text(
-        tokenizer: &Tokenizer,
-        text: &str,
+    /// one forward over `tokens`, the indices of the tokens of `encoding` to feed to the model
+    fn forward(
+        bert: &tch::CModule,
+        encoding: &Encoding,
+        tokens: &[usize],
+        word2ph: &[i32],
         device: tch::Device,
-    ) -> (Tensor, Tensor, Tensor) {
-        let encoding = tokenizer.encode(text, true).unwrap();
-        let ids = encoding
-            .get_ids()
-            .iter()
-            .map(|x| (*x) as i32)
-            .collect::<Vec<i32>>();
-        let text_ids = Tensor::from_slice(&ids);
-        let text_ids = text_ids.unsqueeze(0).to_device(device);
-
-        let mask = encoding
-            .get_attention_mask()
-            .iter()
-            .map(|x| (*x) as i32)
-            .collect::<Vec<i32>>();
-        let text_mask = Tensor::from_slice(&mask);
-        let text_mask = text_mask.unsqueeze(0).to_device(device);
-
-        let token_type_ids = encoding
-            .get_type_ids()
-            .iter()
-            .map(|x| (*x) as i32)
-            .collect::<Vec<i32>>();
-        let text_token_type_ids = Tensor::from_slice(&token_type_ids);
-        let text_token_type_ids = text_token_type_ids.unsqueeze(0).to_device(device);
-        (text_ids, text_mask, text_token_type_ids)
+    ) -> Result<Tensor> {
+        let tensor = |values: &[u32]| {
+            let values = tokens.iter().map(|&i| values[i] as i32).collect::<Vec<_>>();
+            Tensor::from_slice(&values).unsqueeze(0).to_device(device)
+        };
+        let text_ids = tensor(encoding.get_ids());
+        let text_mask = tensor(encoding.get_attention_mask());
+        let text_token_type_ids = tensor(encoding.get_type_ids());
+        let text_word2ph = Tensor::from_slice(word2ph).to_device(device);
+
+        Ok(bert
+            .forward_ts(&[&text_ids, &text_mask, &text_token_type_ids, &text_word2ph])?
+            .to_device(device))
     }
 
     pub fn get_text_bert(
@@ -716,12 +1063,67 @@ impl CNBertModel {
                 Tensor::zeros([len as i64, 1024], (Kind::Float, device))
             }
             CNBertModel::TchBert(bert, tokenizer) => {
-                let (text_ids, text_mask, text_token_type_ids) =
-                    Self::encode_text(tokenizer, text, device);
-                let text_word2ph = Tensor::from_slice(word2ph).to_device(device);
+                let encoding = tokenizer.encode(text, true)?;
+                let special = encoding.get_special_tokens_mask();
+                let (specials, body): (Vec<usize>, Vec<usize>) =
+                    (0..encoding.len()).partition(|&i| special[i] != 0);
+                if body.len() <= BERT_MAX_TOKENS {
+                    let tokens = (0..encoding.len()).collect::<Vec<_>>();
+                    return Self::forward(bert, &encoding, &tokens, word2ph, device);
+                }
 
-                bert.forward_ts(&[&text_ids, &text_mask, &text_token_type_ids, &text_word2ph])?
-                    .to_device(device)
+                // a window only works when each token is one char with its phones
+                let offsets = encoding.get_offsets();
+                let starts = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+                if body.len() != word2ph.len()
+                    || body.len() != starts.len()
+                    || body.iter().zip(&starts).any(|(&t, &s)| offsets[t].0 != s)
+                {
+                    return Err(NihilityGsvError::Infer(format!(
+                        "can not align {} bert tokens with {} chars, the text is too long for one forward",
+                        body.len(),
+                        word2ph.len()
+                    )));
+                }
+                let (head, tail): (Vec<usize>, Vec<usize>) =
+                    specials.into_iter().partition(|&i| i < body[0]);
+
+                // each window sees some context on both sides, a token outside its own part
+                // gets no phones, so the features of the windows are just concatenated
+                let len = body.len();
+                let step = BERT_MAX_TOKENS - 2 * BERT_OVERLAP;
+                let mut features = vec![];
+                for start in (0..len).step_by(step) {
+                    let end = (start + step).min(len);
+                    let (cs, ce) = (
+                        start.saturating_sub(BERT_OVERLAP),
+                        (end + BERT_OVERLAP).min(len),
+                    );
+                    let tokens = head
+                        .iter()
+                        .chain(&body[cs..ce])
+                        .chain(&tail)
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let window_word2ph = (cs..ce)
+                        .map(|i| {
+                            if (start..end).contains(&i) {
+                                word2ph[i]
+                            } else {
+                                0
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    debug!("bert window: {}..{} of {}", start, end, len);
+                    features.push(Self::forward(
+                        bert,
+                        &encoding,
+                        &tokens,
+                        &window_word2ph,
+                        device,
+                    )?);
+                }
+                Tensor::cat(&features, 0)
             }
         };
 
@@ -735,10 +1137,23 @@ struct ZhSentence {
     phones: Vec<g2pw::G2PWOut>,
     word2ph: Vec<i32>,
     zh_text: String,
+    /// phones from the inline markup, kept over the polyphone rules
+    pinned: Vec<usize>,
 }
 
 impl ZhSentence {
-    fn generate_pinyin(&mut self, g2p: &G2p) {
+    fn generate_pinyin(&mut self, g2p: &G2p, lookup: &mut PhoneLookup) {
+        let pinned = self
+            .pinned
+            .iter()
+            .map(|&i| (i, self.phones[i]))
+            .collect::<Vec<_>>();
+        g2p.polyphone
+            .apply(&g2p.jieba, g2p.jieba_hmm, &self.zh_text, &mut self.phones);
+        for (i, p) in pinned {
+            self.phones[i] = p;
+        }
+
         let pinyin = match g2p.g2pw.get_pinyin(&self.zh_text) {
             Ok(pinyin) => pinyin,
             Err(e) => {
@@ -747,8 +1162,6 @@ impl ZhSentence {
             }
         };
 
-        debug_assert_eq!(pinyin.len(), self.phones.len());
-
         debug!("pinyin: {:?}", pinyin);
 
         if pinyin.len() != self.phones.len() {
@@ -773,13 +1186,12 @@ impl ZhSentence {
             match p {
                 g2pw::G2PWOut::Pinyin(p) => {
                     let (s, y) = split_zh_ph(p);
-                    self.phones_ids.push(get_phone_symbol(&g2p.symbols, s));
-                    self.phones_ids.push(get_phone_symbol(&g2p.symbols, y));
+                    self.phones_ids.push(lookup.id(s));
+                    self.phones_ids.push(lookup.id(y));
                     self.word2ph.push(2);
                 }
                 g2pw::G2PWOut::RawChar(c) => {
-                    self.phones_ids
-                        .push(get_phone_symbol(&g2p.symbols, c.to_string().as_str()));
+                    self.phones_ids.push(lookup.id(c.to_string().as_str()));
                     self.word2ph.push(1);
                 }
             }
@@ -802,6 +1214,8 @@ impl ZhSentence {
 #[derive(PartialEq, Eq)]
 enum EnWord {
     Word(String),
+    /// a word read with the phones of the inline markup
+    Pinned(String, Vec<&'static str>),
     Punctuation(&'static str),
     A,
 }
@@ -810,6 +1224,7 @@ impl Debug for EnWord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             EnWord::Word(w) => write!(f, "\"{}\"", w),
+            EnWord::Pinned(w, phones) => write!(f, "\"{}\"{:?}", w, phones),
             EnWord::Punctuation(p) => write!(f, "\"{}\"", p),
             EnWord::A => write!(f, "a"),
         }
@@ -824,46 +1239,79 @@ struct EnSentence {
 }
 
 impl EnSentence {
-    fn generate_phones(&mut self, g2p: &G2p) {
+    fn generate_phones(&mut self, g2p: &G2p, lookup: &mut PhoneLookup) {
         trace!("EnSentence text: {:?}", self.en_text);
-        let symbols = &g2p.symbols;
-        for word in &self.en_text {
+        let dicts = dict::snapshot();
+        let parts = self
+            .en_text
+            .iter()
+            .map(|word| match word {
+                EnWord::Word(w) if dicts.en_word(w).is_none() => acronym::parts(w, &dicts),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        // only out of vocabulary words go to the model, all of them at once
+        let oov = parts
+            .iter()
+            .flatten()
+            .filter_map(|part| match part {
+                acronym::Part::Word(w) if dicts.en_lexicon(w).is_none() => Some(*w),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let decoded = match g2p.g2p_en.get_phonemes(&oov) {
+            Ok(v) => oov.iter().copied().zip(v).collect::<HashMap<_, _>>(),
+            Err(e) => {
+                warn!("spell {:?}: {}", oov, e);
+                HashMap::new()
+            }
+        };
+        for (word, parts) in self.en_text.iter().zip(parts) {
             match word {
                 EnWord::Word(word) => {
-                    if let Some(v) = dict::en_word_dict(word) {
-                        for ph in v {
+                    if let Some(v) = dicts.en_word(word) {
+                        for &ph in v {
                             self.phones.push(Cow::Borrowed(ph));
-                            self.phones_ids.push(get_phone_symbol(symbols, ph));
-                        }
-                    } else if let (false, Ok(v)) = (
-                        word.chars().all(char::is_uppercase),
-                        g2p.g2p_en.get_phoneme(word),
-                    ) {
-                        for ph in v.split_ascii_whitespace() {
-                            self.phones.push(Cow::Owned(ph.to_string()));
-                            self.phones_ids.push(get_phone_symbol(symbols, ph));
+                            self.phones_ids.push(lookup.id(ph));
                         }
                     } else {
-                        for c in word.chars() {
-                            let mut b = [0; 4];
-                            let c = c.encode_utf8(&mut b);
-
-                            if let Ok(v) = g2p.g2p_en.get_phoneme(c) {
-                                for ph in v.split_ascii_whitespace() {
-                                    self.phones.push(Cow::Owned(ph.to_string()));
-                                    self.phones_ids.push(get_phone_symbol(symbols, ph));
-                                }
+                        for part in parts {
+                            let phones = match part {
+                                acronym::Part::Phones(v) => v,
+                                acronym::Part::Word(w) => match dicts.en_lexicon(w) {
+                                    Some(v) => Cow::Borrowed(v),
+                                    None => match decoded.get(w) {
+                                        Some(v) => {
+                                            for ph in v.split_ascii_whitespace() {
+                                                self.phones.push(Cow::Owned(ph.to_string()));
+                                                self.phones_ids.push(lookup.id(ph));
+                                            }
+                                            continue;
+                                        }
+                                        None => Cow::Owned(acronym::spell(w)),
+                                    },
+                                },
+                            };
+                            for &ph in phones.iter() {
+                                self.phones.push(Cow::Borrowed(ph));
+                                self.phones_ids.push(lookup.id(ph));
                             }
                         }
                     }
                 }
+                EnWord::Pinned(_, phones) => {
+                    for ph in phones {
+                        self.phones.push(Cow::Borrowed(ph));
+                        self.phones_ids.push(lookup.id(ph));
+                    }
+                }
                 EnWord::A => {
                     self.phones.push(Cow::Borrowed("AH0"));
-                    self.phones_ids.push(get_phone_symbol(symbols, "AH0"));
+                    self.phones_ids.push(lookup.id("AH0"));
                 }
                 EnWord::Punctuation(p) => {
                     self.phones.push(Cow::Borrowed(p));
-                    self.phones_ids.push(get_phone_symbol(symbols, p));
+                    self.phones_ids.push(lookup.id(p));
                 }
             }
         }
@@ -891,13 +1339,16 @@ struct JpSentence {
 
 #[cfg(feature = "enable_jp")]
 impl JpSentence {
-    fn build_phone_and_bert(&self, g2p: &G2p) -> Result<(Tensor, Tensor)> {
+    fn build_phone_and_bert(
+        &self,
+        g2p: &G2p,
+        lookup: &mut PhoneLookup,
+    ) -> Result<(Tensor, Tensor)> {
         let phones = g2p.g2p_jp.g2p(self.text.as_str());
         trace!("JpSentence phones: {:?}", phones);
-        let symbols = &g2p.symbols;
         let phone_ids = phones
             .into_iter()
-            .map(|v| get_phone_symbol(symbols, v.as_str()))
+            .map(|v| lookup.id(v.as_str()))
             .collect::<Vec<_>>();
         let t = Tensor::from_slice(&phone_ids)
             .to_device(g2p.device)
@@ -907,19 +1358,183 @@ impl JpSentence {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Lang {
+#[cfg(feature = "enable_ko")]
+#[derive(Debug)]
+struct KoSentence {
+    text: String,
+}
+
+#[cfg(feature = "enable_ko")]
+impl KoSentence {
+    fn build_phone_and_bert(
+        &self,
+        g2p: &G2p,
+        lookup: &mut PhoneLookup,
+    ) -> Result<(Tensor, Tensor)> {
+        let phones = g2p_ko::g2p(self.text.as_str());
+        trace!("KoSentence phones: {:?}", phones);
+        let phone_ids = phones
+            .into_iter()
+            .map(|v| lookup.id(v.as_str()))
+            .collect::<Vec<_>>();
+        let t = Tensor::from_slice(&phone_ids)
+            .to_device(g2p.device)
+            .unsqueeze(0);
+        let bert = Tensor::zeros(&[phone_ids.len() as i64, 1024], (Kind::Float, g2p.device));
+        Ok((t, bert))
+    }
+}
+
+#[cfg(feature = "enable_yue")]
+#[derive(Debug)]
+struct YueSentence {
+    text: String,
+}
+
+#[cfg(feature = "enable_yue")]
+impl YueSentence {
+    fn build_phone_and_bert(
+        &self,
+        g2p: &G2p,
+        lookup: &mut PhoneLookup,
+    ) -> Result<(Tensor, Tensor)> {
+        let phones = g2p_yue::g2p(self.text.as_str());
+        trace!("YueSentence phones: {:?}", phones);
+        let phone_ids = phones
+            .into_iter()
+            .map(|v| lookup.id(v.as_str()))
+            .collect::<Vec<_>>();
+        let t = Tensor::from_slice(&phone_ids)
+            .to_device(g2p.device)
+            .unsqueeze(0);
+        let bert = Tensor::zeros(&[phone_ids.len() as i64, 1024], (Kind::Float, g2p.device));
+        Ok((t, bert))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
     Zh,
     En,
+    #[cfg(feature = "enable_jp")]
+    #[serde(alias = "ja")]
+    Jp,
+    #[cfg(feature = "enable_ko")]
+    Ko,
+    #[cfg(feature = "enable_yue")]
+    Yue,
+}
+
+/// Language of the request text, same as `text_language` of GPT-SoVITS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextLanguage {
+    /// detect the language of each word
+    #[default]
+    Auto,
+    /// chinese mixed with english
+    Zh,
+    /// english only
+    En,
+    /// japanese mixed with english
+    Ja,
+    /// chinese only
+    AllZh,
+    /// japanese only
+    AllJa,
+    /// korean mixed with english
+    Ko,
+    /// korean only
+    AllKo,
+    /// cantonese mixed with english
+    Yue,
+    /// cantonese only
+    AllYue,
+}
+
+impl TextLanguage {
+    pub fn need_jp(&self) -> bool {
+        matches!(self, TextLanguage::Ja | TextLanguage::AllJa)
+    }
+
+    pub fn need_ko(&self) -> bool {
+        matches!(self, TextLanguage::Ko | TextLanguage::AllKo)
+    }
+
+    pub fn need_yue(&self) -> bool {
+        matches!(self, TextLanguage::Yue | TextLanguage::AllYue)
+    }
+
+    fn num_lang(&self) -> Option<Lang> {
+        match self {
+            TextLanguage::En => Some(Lang::En),
+            TextLanguage::AllZh => Some(Lang::Zh),
+            #[cfg(feature = "enable_jp")]
+            TextLanguage::AllJa => Some(Lang::Jp),
+            #[cfg(feature = "enable_ko")]
+            TextLanguage::AllKo => Some(Lang::Ko),
+            #[cfg(feature = "enable_yue")]
+            TextLanguage::AllYue => Some(Lang::Yue),
+            _ => None,
+        }
+    }
+
+    /// the language urls and paths are read in, `None` follows the text around them
+    fn token_lang(&self) -> Option<Lang> {
+        match self {
+            TextLanguage::En => Some(Lang::En),
+            TextLanguage::AllZh => Some(Lang::Zh),
+            #[cfg(feature = "enable_jp")]
+            TextLanguage::Ja | TextLanguage::AllJa => Some(Lang::Jp),
+            #[cfg(feature = "enable_ko")]
+            TextLanguage::Ko | TextLanguage::AllKo => Some(Lang::Ko),
+            #[cfg(feature = "enable_yue")]
+            TextLanguage::Yue | TextLanguage::AllYue => Some(Lang::Yue),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for TextLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TextLanguage::Auto),
+            "zh" => Ok(TextLanguage::Zh),
+            "en" => Ok(TextLanguage::En),
+            "ja" => Ok(TextLanguage::Ja),
+            "all_zh" => Ok(TextLanguage::AllZh),
+            "all_ja" => Ok(TextLanguage::AllJa),
+            "ko" => Ok(TextLanguage::Ko),
+            "all_ko" => Ok(TextLanguage::AllKo),
+            "yue" => Ok(TextLanguage::Yue),
+            "all_yue" => Ok(TextLanguage::AllYue),
+            _ => Err(format!("unknown text language: {}", s)),
+        }
+    }
+}
+
+/// Per request options of the text frontend
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    pub language: TextLanguage,
+    /// read every number in this language instead of following its neighbours
+    pub num_lang: Option<Lang>,
+    pub policy: ErrorPolicy,
 }
 
 #[derive(Debug)]
 struct NumSentence {
     num_text: String,
     lang: Lang,
+    hint: num::NumHint,
 }
 
-static NUM_OP: [char; 8] = ['+', '-', '*', '×', '/', '÷', '=', '%'];
+static NUM_OP: [char; 10] = ['+', '-', '*', '×', '/', '÷', '=', '%', '^', '⁻'];
+/// math signs that are read even without a number next to them
+static MATH_SIGNS: [char; 7] = ['√', '∛', '≥', '≤', '≠', '≈', '±'];
 
 impl NumSentence {
     fn need_drop(&self) -> bool {
@@ -937,11 +1552,18 @@ impl NumSentence {
         //     Lang::En => text::num_to_en_text(symbols, &self.num_text, last_char_is_punctuation),
         // }
         let mut builder = PhoneBuilder::new(false);
-        let pairs = num::ExprParser::parse(num::Rule::all, &self.num_text).map_err(Box::new)?;
+        let num_text = num::fold_superscripts(&self.num_text);
+        let pairs = num::ExprParser::parse(num::Rule::all, &num_text).map_err(Box::new)?;
         for pair in pairs {
             match self.lang {
-                Lang::Zh => num::zh::parse_all(pair, &mut builder)?,
-                Lang::En => num::en::parse_all(pair, &mut builder)?,
+                Lang::Zh => num::parse_all::<num::zh::Zh>(pair, &mut builder, self.hint)?,
+                Lang::En => num::parse_all::<num::en::En>(pair, &mut builder, self.hint)?,
+                #[cfg(feature = "enable_jp")]
+                Lang::Jp => num::parse_all::<num::jp::Jp>(pair, &mut builder, self.hint)?,
+                #[cfg(feature = "enable_ko")]
+                Lang::Ko => num::parse_all::<num::ko::Ko>(pair, &mut builder, self.hint)?,
+                #[cfg(feature = "enable_yue")]
+                Lang::Yue => num::parse_all::<num::yue::Yue>(pair, &mut builder, self.hint)?,
             }
         }
 
@@ -955,13 +1577,98 @@ enum Sentence {
     En(EnSentence),
     #[cfg(feature = "enable_jp")]
     Jp(JpSentence),
+    #[cfg(feature = "enable_ko")]
+    Ko(KoSentence),
+    #[cfg(feature = "enable_yue")]
+    Yue(YueSentence),
     Num(NumSentence),
 }
 
+impl Sentence {
+    /// (language, touches the start, touches the end), spaces do not separate a number from
+    /// its neighbour and the language is None for punctuation only
+    fn num_neighbour(&self) -> (Option<Lang>, bool, bool) {
+        match self {
+            Sentence::Zh(zh) => {
+                let is_char = |p: Option<&g2pw::G2PWOut>| !matches!(p, Some(g2pw::G2PWOut::RawChar(c)) if *c != ' ');
+                (
+                    Some(Lang::Zh),
+                    is_char(zh.phones.first()),
+                    is_char(zh.phones.last()),
+                )
+            }
+            Sentence::En(en) => {
+                let is_word =
+                    |w: Option<&EnWord>| !matches!(w, Some(EnWord::Punctuation(p)) if *p != " ");
+                let has_word = en
+                    .en_text
+                    .iter()
+                    .any(|w| !matches!(w, EnWord::Punctuation(_)));
+                (
+                    has_word.then_some(Lang::En),
+                    is_word(en.en_text.first()),
+                    is_word(en.en_text.last()),
+                )
+            }
+            #[cfg(feature = "enable_jp")]
+            Sentence::Jp(jp) => (
+                Some(Lang::Jp),
+                jp.text.starts_with(is_jp_char),
+                jp.text.ends_with(is_jp_char),
+            ),
+            #[cfg(feature = "enable_ko")]
+            Sentence::Ko(ko) => (
+                Some(Lang::Ko),
+                ko.text.starts_with(is_hangul),
+                ko.text.ends_with(is_hangul),
+            ),
+            #[cfg(feature = "enable_yue")]
+            Sentence::Yue(yue) => (
+                Some(Lang::Yue),
+                yue.text.starts_with(is_han),
+                yue.text.ends_with(is_han),
+            ),
+            Sentence::Num(_) => (None, false, false),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Sentence::Zh(zh) => zh.zh_text.clone(),
+            Sentence::En(en) => en
+                .en_text
+                .iter()
+                .filter_map(|w| match w {
+                    EnWord::Word(w) | EnWord::Pinned(w, _) => Some(w.as_str()),
+                    EnWord::A => Some("a"),
+                    EnWord::Punctuation(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            #[cfg(feature = "enable_jp")]
+            Sentence::Jp(jp) => jp.text.clone(),
+            #[cfg(feature = "enable_ko")]
+            Sentence::Ko(ko) => ko.text.clone(),
+            #[cfg(feature = "enable_yue")]
+            Sentence::Yue(yue) => yue.text.clone(),
+            Sentence::Num(num) => num.num_text.clone(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PhoneBuilder {
     sentence: LinkedList<Sentence>,
     enable_jp: bool,
+    enable_ko: bool,
+    enable_yue: bool,
+    language: TextLanguage,
+    num_lang: Option<Lang>,
+    hmm: bool,
+    policy: ErrorPolicy,
+    /// the words that could not be read
+    unread: Vec<String>,
+    dicts: Arc<dict::Dicts>,
 }
 
 fn parse_punctuation(p: &str) -> Option<&'static str> {
@@ -997,12 +1704,25 @@ fn parse_punctuation(p: &str) -> Option<&'static str> {
 fn is_numeric(p: &str) -> bool {
     p.chars().any(|c| c.is_numeric())
         || p.contains(NUM_OP)
+        || p.contains(MATH_SIGNS)
         || p.to_lowercase().contains([
             'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ',
             'σ', 'ς', 'τ', 'υ', 'φ', 'χ', 'ψ', 'ω',
         ])
 }
 
+/// ":" in "3:2" and "~" in "10~20" stay inside the number, so do "<" and ">" in "3<4" while
+/// they are quotes anywhere else
+fn num_link(p: &str) -> Option<&'static str> {
+    match p {
+        ":" | "：" => Some(":"),
+        "~" | "～" | "–" | "—" => Some("~"),
+        "<" => Some("<"),
+        ">" => Some(">"),
+        _ => None,
+    }
+}
+
 fn is_jp_kana(p: &str) -> bool {
     p.chars().all(|v| {
         let code = v as u32;
@@ -1010,40 +1730,376 @@ fn is_jp_kana(p: &str) -> bool {
     })
 }
 
+fn is_jp_char(c: char) -> bool {
+    matches!(c,
+        '\u{3005}' | '\u{3006}' |                         // 々 〆
+        '\u{3040}'..='\u{30ff}' |                         // Hiragana and Katakana
+        '\u{3400}'..='\u{4dbf}' |                         // CJK Extension A
+        '\u{4e00}'..='\u{9fff}' |                         // CJK Unified Ideographs
+        '\u{f900}'..='\u{faff}'                           // CJK Compatibility Ideographs
+    )
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4dbf}' |                         // CJK Extension A
+        '\u{4e00}'..='\u{9fff}' |                         // CJK Unified Ideographs
+        '\u{f900}'..='\u{faff}' |                         // CJK Compatibility Ideographs
+        '\u{20000}'..='\u{2a6df}'                         // CJK Extension B
+    )
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11ff}' |                         // Hangul Jamo
+        '\u{3131}'..='\u{318e}' |                         // Hangul Compatibility Jamo
+        '\u{ac00}'..='\u{d7a3}'                           // Hangul Syllables
+    )
+}
+
+/// Which g2p a piece of text goes to before jieba
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Other,
+    Jp,
+    Ko,
+}
+
+/// Split text into runs of the chars matched by `is_script` and the rest
+fn split_runs(text: &str, script: Script, is_script: fn(char) -> bool) -> Vec<(&str, Script)> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut matched = false;
+    for (i, c) in text.char_indices() {
+        let is = is_script(c);
+        if i == start {
+            matched = is;
+        } else if is != matched {
+            pieces.push((
+                &text[start..i],
+                if matched { script } else { Script::Other },
+            ));
+            start = i;
+            matched = is;
+        }
+    }
+    if start < text.len() {
+        pieces.push((&text[start..], if matched { script } else { Script::Other }));
+    }
+    pieces
+}
+
+/// A clause with kana is japanese, kanji and kana in it are kept together for jpreprocess,
+/// digits and latin still go through jieba.
+fn split_jp(text: &str, force: bool) -> Vec<(&str, Script)> {
+    let mut pieces = vec![];
+    for clause in
+        text.split_inclusive(['。', '！', '？', '!', '?', '，', ',', '、', '；', ';', '\n'])
+    {
+        if !force && !clause.chars().any(|c| matches!(c, '\u{3040}'..='\u{30ff}')) {
+            pieces.push((clause, Script::Other));
+        } else {
+            pieces.extend(split_runs(clause, Script::Jp, is_jp_char));
+        }
+    }
+    pieces
+}
+
 impl PhoneBuilder {
     pub fn new(enable_jp: bool) -> Self {
         Self {
             sentence: LinkedList::new(),
             enable_jp,
+            enable_ko: false,
+            enable_yue: false,
+            language: TextLanguage::Auto,
+            num_lang: None,
+            hmm: true,
+            policy: ErrorPolicy::default(),
+            unread: vec![],
+            dicts: dict::snapshot(),
         }
     }
 
+    pub fn with_ko(self, enable_ko: bool) -> Self {
+        Self { enable_ko, ..self }
+    }
+
+    pub fn with_yue(self, enable_yue: bool) -> Self {
+        Self { enable_yue, ..self }
+    }
+
+    pub fn with_language(self, language: TextLanguage) -> Self {
+        Self { language, ..self }
+    }
+
+    pub fn with_num_lang(self, num_lang: Option<Lang>) -> Self {
+        Self { num_lang, ..self }
+    }
+
+    pub fn with_hmm(self, hmm: bool) -> Self {
+        Self { hmm, ..self }
+    }
+
+    pub fn with_policy(self, policy: ErrorPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
     pub fn push_text(&mut self, jieba: &jieba_rs::Jieba, text: &str) {
-        let mut lang = Lang::En;
-        let r = jieba.cut(text, true);
+        // numbers of an earlier text keep the language resolved with its options
+        let start = self.sentence.len();
+        for span in markup::parse(text) {
+            match span {
+                markup::Span::Text(t) => self.push_plain_text(jieba, t),
+                markup::Span::Zh(word, pinyin) => self.push_zh_pinyin(word, &pinyin),
+                markup::Span::En(word, phones) => self.push_en_phones(word, phones),
+            }
+        }
+
+        let texts = self.sentence.iter().map(Sentence::text).collect::<Vec<_>>();
+        let langs = self
+            .sentence
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s {
+                Sentence::Num(_) if i >= start => Some(
+                    self.num_lang
+                        .or(self.language.num_lang())
+                        .unwrap_or_else(|| self.resolve_num_lang(i)),
+                ),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (i, s) in self.sentence.iter_mut().enumerate().skip(start) {
+            if let Sentence::Num(s) = s {
+                s.lang = langs[i].unwrap_or(s.lang);
+                let prev = i.checked_sub(1).map_or("", |i| texts[i].as_str());
+                let next = texts.get(i + 1).map_or("", |t| t.as_str());
+                s.hint = num::NumHint::from_context(prev, next);
+            }
+        }
+        #[cfg(any(feature = "enable_jp", feature = "enable_ko", feature = "enable_yue"))]
+        self.attach_counters();
+    }
+
+    fn push_plain_text(&mut self, jieba: &jieba_rs::Jieba, text: &str) {
+        let text = normalize::token::normalize(text, self.language.token_lang());
+        let text = normalize::numeral::normalize(&text);
+        let text = normalize::en::normalize(&text);
+        let mut pieces = match self.language {
+            TextLanguage::Auto | TextLanguage::Ja | TextLanguage::AllJa if self.enable_jp => {
+                split_jp(&text, self.language.need_jp())
+            }
+            _ => vec![(&*text, Script::Other)],
+        };
+        if self.enable_ko
+            && matches!(
+                self.language,
+                TextLanguage::Auto | TextLanguage::Ko | TextLanguage::AllKo
+            )
+        {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|(piece, script)| match script {
+                    Script::Other => split_runs(piece, Script::Ko, is_hangul),
+                    script => vec![(piece, script)],
+                })
+                .collect();
+        }
+        let mut r = vec![];
+        for (piece, script) in pieces {
+            match script {
+                Script::Other => {
+                    r.extend(jieba.cut(piece, self.hmm).into_iter().map(|t| (t, script)))
+                }
+                script => r.push((piece, script)),
+            }
+        }
         info!("jieba cut: {:?}", r);
-        for t in r {
-            if is_numeric(t) {
+        for (i, (t, script)) in r.iter().copied().enumerate() {
+            let link = num_link(t).filter(|link| {
+                let mut next = r[i + 1..].iter().map(|(n, _)| *n).peekable();
+                // "3>=2" and "5<-3" are compared too
+                if matches!(*link, "<" | ">") {
+                    next.next_if_eq(&"=");
+                    next.next_if_eq(&"-");
+                }
+                self.back_is_number()
+                    && next
+                        .next()
+                        .is_some_and(|n| n.starts_with(|c: char| c.is_ascii_digit()))
+            });
+            if script == Script::Jp {
+                #[cfg(feature = "enable_jp")]
+                self.push_jp_word(t);
+            } else if script == Script::Ko {
+                #[cfg(feature = "enable_ko")]
+                self.push_ko_word(t);
+            } else if let Some(link) = link {
+                self.push_num_word(link);
+            } else if is_numeric(t) {
                 self.push_num_word(t);
             } else if let Some(p) = parse_punctuation(t) {
                 self.push_punctuation(p);
-            } else if g2pw::str_is_chinese(t) {
-                self.push_zh_word(t);
-                lang = Lang::Zh;
-            } else if t.is_ascii() {
-                self.push_en_word(t);
-            } else if self.enable_jp && is_jp_kana(t) {
+            } else if !self.push_word(t) {
+                debug!("can not read word: {:?} in {}", t, text);
+                self.unread.push(t.to_string());
+                if self.policy == ErrorPolicy::Replace {
+                    self.replace_word(t);
+                }
+            }
+        }
+    }
+
+    /// Move the counter after a japanese, korean or cantonese number into it, "3本" is read as
+    /// さんぼん, "3개" as 세 개 and "2個" as 兩個
+    #[cfg(any(feature = "enable_jp", feature = "enable_ko", feature = "enable_yue"))]
+    fn attach_counters(&mut self) {
+        let mut iter = self.sentence.iter_mut().peekable();
+        while let Some(s) = iter.next() {
+            let Sentence::Num(n) = s else {
+                continue;
+            };
+            let (text, counter): (&mut String, fn(&str) -> Option<&'static str>) =
+                match (n.lang, iter.peek_mut()) {
+                    #[cfg(feature = "enable_jp")]
+                    (Lang::Jp, Some(Sentence::Jp(jp))) => (&mut jp.text, num::jp::counter),
+                    #[cfg(feature = "enable_ko")]
+                    (Lang::Ko, Some(Sentence::Ko(ko))) => (&mut ko.text, num::ko::counter),
+                    #[cfg(feature = "enable_yue")]
+                    (Lang::Yue, Some(Sentence::Yue(yue))) => (&mut yue.text, num::yue::counter),
+                    _ => continue,
+                };
+            if let Some(counter) = counter(text) {
+                text.replace_range(..counter.len(), "");
+                n.hint = num::NumHint::Counter(counter);
+            }
+        }
+        self.sentence = std::mem::take(&mut self.sentence)
+            .into_iter()
+            .filter(|s| match s {
                 #[cfg(feature = "enable_jp")]
-                self.push_jp_word(t);
-            } else {
-                warn!("skip word: {:?} in {}", t, text);
+                Sentence::Jp(jp) => !jp.text.is_empty(),
+                #[cfg(feature = "enable_ko")]
+                Sentence::Ko(ko) => !ko.text.is_empty(),
+                #[cfg(feature = "enable_yue")]
+                Sentence::Yue(yue) => !yue.text.is_empty(),
+                _ => true,
+            })
+            .collect();
+    }
+
+    /// chars that are not in the cantonese dict are unread like a word that can not be read
+    #[cfg(feature = "enable_yue")]
+    fn push_yue_text(&mut self, t: &str) {
+        let mut start = 0;
+        for (i, c) in g2p_yue::unknown(t, &self.dicts) {
+            if start < i {
+                self.push_yue_word(&t[start..i]);
+            }
+            debug!("can not read cantonese char: {:?} in {}", c, t);
+            self.unread.push(c.to_string());
+            if self.policy == ErrorPolicy::Replace {
+                self.replace_word(&c.to_string());
             }
+            start = i + c.len_utf8();
+        }
+        if start < t.len() {
+            self.push_yue_word(&t[start..]);
         }
+    }
 
-        for s in self.sentence.iter_mut() {
-            if let Sentence::Num(s) = s {
-                s.lang = lang;
+    /// letters are spelled and anything else is a pause
+    fn replace_word(&mut self, t: &str) {
+        let letters = acronym::spell(t);
+        if letters.is_empty() {
+            self.push_punctuation(",");
+        } else {
+            self.push_en_phones("", letters);
+        }
+    }
+
+    /// push a word by the request language, return false if the word can not be read
+    fn push_word(&mut self, t: &str) -> bool {
+        let jp = self.enable_jp
+            && match self.language {
+                TextLanguage::Auto => is_jp_kana(t),
+                TextLanguage::Ja => !t.is_ascii(),
+                TextLanguage::AllJa => true,
+                _ => false,
+            };
+        if jp {
+            #[cfg(feature = "enable_jp")]
+            self.push_jp_word(t);
+            return cfg!(feature = "enable_jp");
+        }
+        if self.enable_ko && self.language == TextLanguage::AllKo {
+            #[cfg(feature = "enable_ko")]
+            self.push_ko_word(t);
+            return cfg!(feature = "enable_ko");
+        }
+        if self.enable_yue && self.language.need_yue() && t.chars().all(is_han) {
+            #[cfg(feature = "enable_yue")]
+            self.push_yue_text(t);
+            return cfg!(feature = "enable_yue");
+        }
+        match self.language {
+            TextLanguage::Auto | TextLanguage::Zh | TextLanguage::AllZh
+                if g2pw::str_is_chinese(t) =>
+            {
+                self.push_zh_word(t)
+            }
+            TextLanguage::Auto
+            | TextLanguage::Zh
+            | TextLanguage::En
+            | TextLanguage::Ja
+            | TextLanguage::Ko
+            | TextLanguage::Yue
+                if t.is_ascii() =>
+            {
+                self.push_en_word(t)
             }
+            _ => return false,
+        }
+        true
+    }
+
+    /// A number is read in the language of the segment it touches, "3个" is Chinese and
+    /// "3 apples" is English. Without such a segment the nearest language is used.
+    fn resolve_num_lang(&self, index: usize) -> Lang {
+        fn find<'a>(
+            mut iter: impl Iterator<Item = &'a Sentence>,
+            at_end: bool,
+        ) -> Option<(Lang, bool)> {
+            let mut tight = true;
+            loop {
+                match iter.next()?.num_neighbour() {
+                    (Some(lang), head, tail) => {
+                        return Some((lang, tight && if at_end { tail } else { head }));
+                    }
+                    (None, _, _) => tight = false,
+                }
+            }
+        }
+
+        let prev = find(self.sentence.iter().take(index).rev(), true);
+        let next = find(self.sentence.iter().skip(index + 1), false);
+        match (prev, next) {
+            (Some((p, _)), Some((n, _))) if p == n => p,
+            (Some((p, true)), Some((_, false))) => p,
+            (Some(_), Some((n, true))) => n,
+            (Some((p, _)), _) => p,
+            (None, Some((n, _))) => n,
+            (None, None) => Lang::En,
+        }
+    }
+
+    fn back_is_number(&self) -> bool {
+        match self.sentence.back() {
+            Some(Sentence::Num(n)) => n
+                .num_text
+                .ends_with(|c: char| c.is_ascii_digit() || c == '%'),
+            _ => false,
         }
     }
 
@@ -1088,6 +2144,18 @@ impl PhoneBuilder {
                     en_text: vec![EnWord::Punctuation(p)],
                 }));
             }
+            #[cfg(feature = "enable_jp")]
+            Some(Sentence::Jp(jp)) => {
+                jp.text.push_str(p);
+            }
+            #[cfg(feature = "enable_ko")]
+            Some(Sentence::Ko(ko)) => {
+                ko.text.push_str(p);
+            }
+            #[cfg(feature = "enable_yue")]
+            Some(Sentence::Yue(yue)) => {
+                yue.text.push_str(p);
+            }
             _ => {
                 debug!("skip punctuation: {}", p);
             }
@@ -1136,11 +2204,11 @@ impl PhoneBuilder {
     }
 
     pub fn push_zh_word(&mut self, word: &str) {
-        fn h(zh: &mut ZhSentence, word: &str) {
+        fn h(zh: &mut ZhSentence, word: &str, dicts: &dict::Dicts) {
             zh.zh_text.push_str(word);
-            match dict::zh_word_dict(word) {
+            match dicts.zh_word(word) {
                 Some(phones) => {
-                    for p in phones {
+                    for &p in phones {
                         zh.phones.push(g2pw::G2PWOut::Pinyin(p));
                     }
                 }
@@ -1154,7 +2222,7 @@ impl PhoneBuilder {
 
         match self.sentence.back_mut() {
             Some(Sentence::Zh(zh)) => {
-                h(zh, word);
+                h(zh, word, &self.dicts);
             }
             Some(Sentence::Num(n)) if n.need_drop() => {
                 self.sentence.pop_back();
@@ -1166,13 +2234,42 @@ impl PhoneBuilder {
                     phones: Vec::new(),
                     word2ph: Vec::new(),
                     zh_text: String::new(),
+                    pinned: Vec::new(),
                 };
-                h(&mut zh, word);
+                h(&mut zh, word, &self.dicts);
                 self.sentence.push_back(Sentence::Zh(zh));
             }
         };
     }
 
+    /// push chinese chars read with the given pinyin, one per char
+    pub fn push_zh_pinyin(&mut self, word: &str, pinyin: &[&'static str]) {
+        self.push_zh_word(word);
+        if let Some(Sentence::Zh(zh)) = self.sentence.back_mut() {
+            let start = zh.phones.len() - pinyin.len();
+            for (i, p) in pinyin.iter().enumerate() {
+                zh.phones[start + i] = g2pw::G2PWOut::Pinyin(p);
+                zh.pinned.push(start + i);
+            }
+        }
+    }
+
+    /// push an english word read with the given ARPAbet, the word may be empty
+    pub fn push_en_phones(&mut self, word: &str, phones: Vec<&'static str>) {
+        if matches!(self.sentence.back(), Some(Sentence::Num(n)) if n.need_drop()) {
+            self.sentence.pop_back();
+        }
+        let word = EnWord::Pinned(word.to_string(), phones);
+        match self.sentence.back_mut() {
+            Some(Sentence::En(en)) => en.en_text.push(word),
+            _ => self.sentence.push_back(Sentence::En(EnSentence {
+                phones_ids: vec![],
+                phones: vec![],
+                en_text: vec![word],
+            })),
+        }
+    }
+
     #[cfg(feature = "enable_jp")]
     pub fn push_jp_word(&mut self, word: &str) {
         match self.sentence.back_mut() {
@@ -1188,18 +2285,50 @@ impl PhoneBuilder {
         }
     }
 
+    #[cfg(feature = "enable_ko")]
+    pub fn push_ko_word(&mut self, word: &str) {
+        match self.sentence.back_mut() {
+            Some(Sentence::Ko(ko)) => {
+                ko.text.push_str(word);
+            }
+            _ => {
+                let ko = KoSentence {
+                    text: word.to_owned(),
+                };
+                self.sentence.push_back(Sentence::Ko(ko));
+            }
+        }
+    }
+
+    #[cfg(feature = "enable_yue")]
+    pub fn push_yue_word(&mut self, word: &str) {
+        match self.sentence.back_mut() {
+            Some(Sentence::Yue(yue)) => {
+                yue.text.push_str(word);
+            }
+            _ => {
+                let yue = YueSentence {
+                    text: word.to_owned(),
+                };
+                self.sentence.push_back(Sentence::Yue(yue));
+            }
+        }
+    }
+
     pub fn push_num_word(&mut self, word: &str) {
         match self.sentence.back_mut() {
             Some(Sentence::Zh(_)) => {
                 self.sentence.push_back(Sentence::Num(NumSentence {
                     num_text: word.to_string(),
                     lang: Lang::Zh,
+                    hint: num::NumHint::Auto,
                 }));
             }
             Some(Sentence::En(_)) => {
                 self.sentence.push_back(Sentence::Num(NumSentence {
                     num_text: word.to_string(),
                     lang: Lang::En,
+                    hint: num::NumHint::Auto,
                 }));
             }
             Some(Sentence::Num(num)) => {
@@ -1209,6 +2338,7 @@ impl PhoneBuilder {
                 self.sentence.push_back(Sentence::Num(NumSentence {
                     num_text: word.to_string(),
                     lang: Lang::En,
+                    hint: num::NumHint::Auto,
                 }));
             }
         }
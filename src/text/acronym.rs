@@ -0,0 +1,121 @@
+//! Acronyms and initialisms in english words, "NASA" is read as a word, "FBI" letter by
+//! letter and "GitHub" is split into "Git" and "Hub" before the en g2p.
+
+use super::dict::Dicts;
+use std::borrow::Cow;
+
+/// letter names in ARPAbet
+static LETTERS: [&[&str]; 26] = [
+    &["EY1"],
+    &["B", "IY1"],
+    &["S", "IY1"],
+    &["D", "IY1"],
+    &["IY1"],
+    &["EH1", "F"],
+    &["JH", "IY1"],
+    &["EY1", "CH"],
+    &["AY1"],
+    &["JH", "EY1"],
+    &["K", "EY1"],
+    &["EH1", "L"],
+    &["EH1", "M"],
+    &["EH1", "N"],
+    &["OW1"],
+    &["P", "IY1"],
+    &["K", "Y", "UW1"],
+    &["AA1", "R"],
+    &["EH1", "S"],
+    &["T", "IY1"],
+    &["Y", "UW1"],
+    &["V", "IY1"],
+    &["D", "AH1", "B", "AH0", "L", "Y", "UW0"],
+    &["EH1", "K", "S"],
+    &["W", "AY1"],
+    &["Z", "IY1"],
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Part<'a> {
+    /// phones from the acronym dict or the letter names
+    Phones(Cow<'a, [&'static str]>),
+    /// a plain word for the en g2p
+    Word(&'a str),
+}
+
+pub fn letter_phones(c: char) -> &'static [&'static str] {
+    if c.is_ascii_alphabetic() {
+        LETTERS[(c.to_ascii_uppercase() as u8 - b'A') as usize]
+    } else {
+        &[]
+    }
+}
+
+pub fn spell(word: &str) -> Vec<&'static str> {
+    word.chars().flat_map(letter_phones).copied().collect()
+}
+
+/// "iPhone" -> i|Phone, "HTTPServer" -> HTTP|Server and "GitHub" -> Git|Hub, while "CDs" and
+/// "McDonald" stay in one piece
+pub fn split_camel(word: &str) -> Vec<&str> {
+    let chars = word.char_indices().collect::<Vec<_>>();
+    let mut parts = vec![];
+    let mut start = 0;
+    for (n, &(i, c)) in chars.iter().enumerate().skip(1) {
+        let prev = chars[n - 1].1;
+        let next = chars.get(n + 1).map(|(_, c)| *c);
+        let plural = next == Some('s') && n + 2 == chars.len();
+        let boundary =
+            (prev.is_ascii_lowercase() && c.is_ascii_uppercase() && &word[start..i] != "Mc")
+                || (prev.is_ascii_uppercase()
+                    && c.is_ascii_uppercase()
+                    && next.is_some_and(|c| c.is_ascii_lowercase())
+                    && !plural);
+        if boundary {
+            parts.push(&word[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+fn is_initialism(word: &str) -> bool {
+    word.chars().all(|c| c.is_ascii_uppercase())
+}
+
+fn part<'a>(word: &'a str, split: bool, dicts: &'a Dicts) -> Part<'a> {
+    if let Some(phones) = dicts.en_acronym(word) {
+        return Part::Phones(Cow::Borrowed(phones));
+    }
+    if is_initialism(word) || (split && word.len() == 1) {
+        return Part::Phones(Cow::Owned(spell(word)));
+    }
+    // CDs and APIs
+    if let Some(letters) = word.strip_suffix('s')
+        && letters.len() > 1
+        && is_initialism(letters)
+    {
+        let mut phones = spell(letters);
+        phones.push("Z");
+        return Part::Phones(Cow::Owned(phones));
+    }
+    Part::Word(word)
+}
+
+/// Split an english word into the pieces that are read as acronyms, spelled or left to the g2p
+pub fn parts<'a>(word: &'a str, dicts: &'a Dicts) -> Vec<Part<'a>> {
+    if let Some(base) = word.strip_suffix("'s")
+        && !base.is_empty()
+        && !matches!(part(base, false, dicts), Part::Word(_))
+    {
+        let mut parts = parts(base, dicts);
+        parts.push(Part::Phones(Cow::Borrowed(&["Z"])));
+        return parts;
+    }
+    if dicts.en_acronym(word).is_some() {
+        return vec![part(word, false, dicts)];
+    }
+    let pieces = split_camel(word);
+    let split = pieces.len() > 1;
+    pieces.into_iter().map(|p| part(p, split, dicts)).collect()
+}
@@ -1,37 +1,359 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::SystemTime;
 
+use crate::error::*;
+use crate::symbols::SYMBOLS;
 use lazy_static::lazy_static;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
 static DEFAULT_ZH_WORD_DICT: &str = include_str!("../../resource/zh_word_dict.json");
+static DEFAULT_EN_ACRONYM_DICT: &str = include_str!("../../resource/en_acronym_dict.json");
+#[cfg(feature = "enable_yue")]
+static DEFAULT_YUE_WORD_DICT: &str = include_str!("../../resource/yue_dict.json");
+#[cfg(feature = "bundled_en_dict")]
+include_flate::flate!(static DEFAULT_EN_LEXICON: str from "resource/cmudict.dict");
+/// common words only, a full cmudict is bundled with `bundled_en_dict` or layered over it with
+/// `en_lexicon`
+#[cfg(not(feature = "bundled_en_dict"))]
+static DEFAULT_EN_LEXICON: &str = include_str!("../../resource/en_lexicon.dict");
+
+type Dict = HashMap<String, Vec<&'static str>>;
+
+/// User dictionaries layered over the bundled ones, a later file overrides the words of an
+/// earlier one. The files in `GPT_SOVITS_DICT_PATH` are the first layer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictConfig {
+    /// word -> pinyin, one per char
+    pub zh: Vec<String>,
+    /// word -> ARPAbet, matched as written before acronyms are spelled
+    pub en: Vec<String>,
+    /// cmudict style lexicons over the bundled common words, like a full `cmudict.dict`,
+    /// matched ignoring case before the en g2p model
+    pub en_lexicon: Vec<String>,
+    /// acronyms read as words -> ARPAbet
+    pub en_acronym: Vec<String>,
+    /// word -> jyutping
+    pub yue: Vec<String>,
+    /// reload before an infer when one of the files changed
+    pub watch: bool,
+}
+
+/// all the dictionaries of one load, take a `snapshot` once and look words up in it
+#[derive(Default)]
+pub struct Dicts {
+    zh: Dict,
+    en: Dict,
+    en_lexicon: Dict,
+    en_acronym: Dict,
+    #[cfg(feature = "enable_yue")]
+    yue: Dict,
+    #[cfg(feature = "enable_yue")]
+    yue_max_len: usize,
+    /// the user files and their mtime when loaded
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl fmt::Debug for Dicts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dicts")
+            .field("files", &self.files)
+            .finish_non_exhaustive()
+    }
+}
 
 lazy_static! {
-    static ref ZN_DICT: HashMap<String, Vec<String>> = {
-        let word_dict_path = std::env::var("GPT_SOVITS_DICT_PATH").unwrap_or(".".to_string());
-        let path = PathBuf::from(word_dict_path.as_str()).join("zh_word_dict.json");
-        if path.is_file() {
-            let zh_word_dict = std::fs::read_to_string(path).unwrap();
-            serde_json::from_str(&zh_word_dict).unwrap()
-        } else {
-            serde_json::from_str(DEFAULT_ZH_WORD_DICT).unwrap()
+    /// every phone is leaked once, so a reload does not grow the memory
+    static ref PHONES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+    static ref CONFIG: RwLock<DictConfig> = RwLock::new(DictConfig::default());
+    /// the mtimes of the user files when a reload failed, so it is not retried until they change
+    static ref FAILED: Mutex<Option<Vec<Option<SystemTime>>>> = Mutex::new(None);
+    static ref DICTS: RwLock<Arc<Dicts>> = RwLock::new(Arc::new(
+        Dicts::load(&DictConfig::default(), true).unwrap_or_else(|e| {
+            warn!("load user dict error: {}, use the bundled dict", e);
+            Dicts::load(&DictConfig::default(), false).unwrap()
+        })
+    ));
+}
+
+fn intern(phone: &str) -> &'static str {
+    let mut phones = PHONES.lock().unwrap_or_else(PoisonError::into_inner);
+    match phones.get(phone) {
+        Some(p) => p,
+        None => {
+            let p: &'static str = Box::leak(phone.to_owned().into_boxed_str());
+            phones.insert(p);
+            p
         }
-    };
-    static ref EN_DICT: HashMap<String, Vec<String>> = {
-        let word_dict_path = std::env::var("GPT_SOVITS_DICT_PATH").unwrap_or(".".to_string());
-        let path = PathBuf::from(word_dict_path.as_str()).join("en_word_dict.json");
-        if path.is_file() {
-            let en_word_dict = std::fs::read_to_string(path).unwrap();
-            serde_json::from_str(&en_word_dict).unwrap()
-        } else {
-            HashMap::default()
+    }
+}
+
+fn mtime(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// `per_char` dicts need one phone for each char of the word, like pinyin, every phone has to
+/// pass `valid`
+fn parse_json(name: &str, json: &str, per_char: bool, valid: fn(&str) -> bool) -> Result<Dict> {
+    let dict: HashMap<String, Vec<String>> =
+        serde_json::from_str(json).map_err(|e| NihilityGsvError::Dict(format!("{name}: {e}")))?;
+    dict.into_iter()
+        .map(|(word, phones)| {
+            if word.is_empty() || phones.is_empty() || phones.iter().any(|p| p.trim().is_empty()) {
+                return Err(NihilityGsvError::Dict(format!(
+                    "{name}: {word:?} has an empty phone"
+                )));
+            }
+            if per_char && word.chars().count() != phones.len() {
+                return Err(NihilityGsvError::Dict(format!(
+                    "{name}: {word:?} has {} phones",
+                    phones.len()
+                )));
+            }
+            if let Some(p) = phones.iter().find(|p| !valid(p.trim())) {
+                return Err(NihilityGsvError::Dict(format!(
+                    "{name}: {word:?} has an unknown phone {p}"
+                )));
+            }
+            Ok((word, phones.iter().map(|p| intern(p.trim())).collect()))
+        })
+        .collect()
+}
+
+fn pinyin(p: &str) -> bool {
+    let (s, y) = super::split_zh_ph(p);
+    SYMBOLS.contains_key(s) && SYMBOLS.contains_key(y)
+}
+
+fn arpabet(p: &str) -> bool {
+    SYMBOLS.contains_key(p)
+}
+
+/// a vowel without a stress gets one, the symbol table only has stressed ARPAbet vowels
+fn stress(phones: &[&str]) -> Vec<String> {
+    let mut primary = phones.iter().any(|p| p.ends_with('1'));
+    phones
+        .iter()
+        .map(|p| {
+            if !SYMBOLS.contains_key(&format!("{p}1")) {
+                return p.to_string();
+            }
+            let stress = if primary { "0" } else { "1" };
+            primary = true;
+            format!("{p}{stress}")
+        })
+        .collect()
+}
+
+/// `WORD  PHONES` lines, `;;;` comments and alternative readings like `WORD(1)` are skipped, a
+/// `#` starts a comment at the end of a line
+fn parse_cmudict(name: &str, text: &str) -> Result<Dict> {
+    let mut dict = Dict::default();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split_once(" #").map_or(line, |(line, _)| line);
+        let mut iter = line.split_whitespace();
+        let Some(word) = iter.next() else {
+            continue;
+        };
+        if word.starts_with(";;;") || word.ends_with(')') {
+            continue;
         }
-    };
+        let phones = iter.map(str::to_ascii_uppercase).collect::<Vec<_>>();
+        let phones = stress(&phones.iter().map(String::as_str).collect::<Vec<_>>());
+        if let Some(p) = phones.iter().find(|p| !SYMBOLS.contains_key(*p)) {
+            return Err(NihilityGsvError::Dict(format!(
+                "{name} line {}: unknown phone {p}",
+                n + 1
+            )));
+        }
+        if phones.is_empty() {
+            return Err(NihilityGsvError::Dict(format!(
+                "{name} line {}: {word:?} has no phones",
+                n + 1
+            )));
+        }
+        let phones = phones.iter().map(|p| intern(p)).collect();
+        dict.entry(word.to_lowercase()).or_insert(phones);
+    }
+    Ok(dict)
+}
+
+impl Dicts {
+    /// the bundled dict, then the file in `GPT_SOVITS_DICT_PATH`, then the configured files
+    fn layer(
+        &mut self,
+        bundled: Option<&str>,
+        name: &str,
+        paths: &[String],
+        user: bool,
+        parse: impl Fn(&str, &str) -> Result<Dict>,
+    ) -> Result<Dict> {
+        let mut dict = match bundled {
+            Some(text) => parse(name, text)?,
+            None => Dict::default(),
+        };
+        let dir = std::env::var("GPT_SOVITS_DICT_PATH").unwrap_or(".".to_string());
+        let env = PathBuf::from(dir).join(name);
+        let paths = (user && env.is_file())
+            .then_some(env)
+            .into_iter()
+            .chain(paths.iter().filter(|_| user).map(PathBuf::from));
+        for path in paths {
+            let modified = mtime(&path);
+            let json = fs::read_to_string(&path)
+                .map_err(|e| NihilityGsvError::Dict(format!("{}: {e}", path.display())))?;
+            dict.extend(parse(&path.display().to_string(), &json)?);
+            self.files.push((path, modified));
+        }
+        Ok(dict)
+    }
+
+    fn load(config: &DictConfig, user: bool) -> Result<Self> {
+        let json =
+            |per_char, valid| move |name: &str, text: &str| parse_json(name, text, per_char, valid);
+        let mut dicts = Dicts::default();
+        dicts.zh = dicts.layer(
+            Some(DEFAULT_ZH_WORD_DICT),
+            "zh_word_dict.json",
+            &config.zh,
+            user,
+            json(true, pinyin),
+        )?;
+        dicts.en = dicts.layer(
+            None,
+            "en_word_dict.json",
+            &config.en,
+            user,
+            json(false, arpabet),
+        )?;
+        let en_lexicon: &str = &DEFAULT_EN_LEXICON;
+        dicts.en_lexicon = dicts.layer(
+            Some(en_lexicon),
+            "en_lexicon.dict",
+            &config.en_lexicon,
+            user,
+            parse_cmudict,
+        )?;
+        dicts.en_acronym = dicts.layer(
+            Some(DEFAULT_EN_ACRONYM_DICT),
+            "en_acronym_dict.json",
+            &config.en_acronym,
+            user,
+            json(false, arpabet),
+        )?;
+        #[cfg(feature = "enable_yue")]
+        {
+            dicts.yue = dicts.layer(
+                Some(DEFAULT_YUE_WORD_DICT),
+                "yue_dict.json",
+                &config.yue,
+                user,
+                json(false, super::g2p_yue::is_jyutping),
+            )?;
+            dicts.yue_max_len = dicts
+                .yue
+                .keys()
+                .map(|w| w.chars().count())
+                .max()
+                .unwrap_or(1);
+        }
+        Ok(dicts)
+    }
+}
+
+/// the current dictionaries, a reload does not change a snapshot that is in use
+pub fn snapshot() -> Arc<Dicts> {
+    DICTS.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Load the user dictionaries of `config`, the current ones are kept on error
+pub fn load(config: &DictConfig) -> Result<()> {
+    let dicts = Dicts::load(config, true)?;
+    info!("load {} user dict files", dicts.files.len());
+    *DICTS.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(dicts);
+    *CONFIG.write().unwrap_or_else(PoisonError::into_inner) = config.clone();
+    *FAILED.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    Ok(())
 }
 
-pub fn zh_word_dict(word: &str) -> Option<&'static [String]> {
-    ZN_DICT.get(word).map(|s| s.as_slice())
+/// Load the configured user dictionaries again
+pub fn reload() -> Result<()> {
+    let config = CONFIG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    load(&config)
 }
 
-pub fn en_word_dict(word: &str) -> Option<&'static [String]> {
-    EN_DICT.get(word).map(|s| s.as_slice())
+/// Reload when `watch` is set and a user file changed since the last load, a file that is
+/// missing or broken is only tried again after it changes
+pub fn reload_if_changed() {
+    if !CONFIG.read().unwrap_or_else(PoisonError::into_inner).watch {
+        return;
+    }
+    let dicts = snapshot();
+    let mtimes = dicts
+        .files
+        .iter()
+        .map(|(path, _)| mtime(path))
+        .collect::<Vec<_>>();
+    if dicts
+        .files
+        .iter()
+        .zip(&mtimes)
+        .all(|((_, loaded), now)| loaded == now)
+        || FAILED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            == Some(&mtimes)
+    {
+        return;
+    }
+    if let Err(e) = reload() {
+        warn!("reload user dict error: {}, keep the old one", e);
+        *FAILED.lock().unwrap_or_else(PoisonError::into_inner) = Some(mtimes);
+    }
+}
+
+impl Dicts {
+    pub fn zh_word(&self, word: &str) -> Option<&[&'static str]> {
+        self.zh.get(word).map(Vec::as_slice)
+    }
+
+    pub fn en_word(&self, word: &str) -> Option<&[&'static str]> {
+        self.en.get(word).map(Vec::as_slice)
+    }
+
+    /// the lexicon reading of an english word, ignoring case
+    pub fn en_lexicon(&self, word: &str) -> Option<&[&'static str]> {
+        let phones = if word.chars().any(char::is_uppercase) {
+            self.en_lexicon.get(&word.to_lowercase())
+        } else {
+            self.en_lexicon.get(word)
+        };
+        phones.map(Vec::as_slice)
+    }
+
+    /// acronyms that are read as words, like NASA
+    pub fn en_acronym(&self, word: &str) -> Option<&[&'static str]> {
+        self.en_acronym.get(word).map(Vec::as_slice)
+    }
+
+    /// jyutping of a cantonese word or char
+    #[cfg(feature = "enable_yue")]
+    pub fn yue_word(&self, word: &str) -> Option<&[&'static str]> {
+        self.yue.get(word).map(Vec::as_slice)
+    }
+
+    /// the longest word in the cantonese dict, in chars
+    #[cfg(feature = "enable_yue")]
+    pub fn yue_max_len(&self) -> usize {
+        self.yue_max_len
+    }
 }
@@ -1,7 +1,14 @@
 // model from cisco-ai/mini-bart-g2p
 
 use crate::error::*;
-use std::{str::FromStr, sync::Arc};
+use lru::LruCache;
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+};
+use tracing::{debug, info, warn};
 
 static MINI_BART_G2P_TOKENIZER: &str = include_str!("../../resource/tokenizer.mini-bart-g2p.json");
 
@@ -12,15 +19,24 @@ static BOS_TOKEN: &str = "<s>";
 #[allow(unused)]
 static EOS_TOKEN: &str = "</s>";
 
-#[allow(unused)]
 static BOS_TOKEN_ID: u32 = 0;
+static PAD_TOKEN_ID: u32 = 1;
 static EOS_TOKEN_ID: u32 = 2;
 
+/// words decoded at once
+const MAX_BATCH: usize = 32;
+
+const CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(4096).unwrap();
+
 #[derive(Clone)]
 pub struct G2PEnConverter {
     model: Arc<tch::CModule>,
     tokenizer: Arc<tokenizers::Tokenizer>,
     device: tch::Device,
+    /// word -> phonemes
+    cache: Arc<Mutex<LruCache<String, String>>>,
+    /// the model takes an attention mask, so words of any length share a padded batch
+    masked: bool,
 }
 
 impl G2PEnConverter {
@@ -35,42 +51,141 @@ impl G2PEnConverter {
 
         let mut model = tch::CModule::load_on_device(model_path, device)?;
         model.set_eval();
+        let masked = Self::accepts_mask(&model, device);
+        info!("g2p en model takes an attention mask: {}", masked);
 
         Ok(Self {
             model: Arc::new(model),
             tokenizer,
             device,
+            cache: Arc::new(Mutex::new(LruCache::new(CACHE_SIZE))),
+            masked,
         })
     }
 
+    /// An export of `forward(input_ids, attention_mask, decoder_input_ids)` pads words into
+    /// one batch, the one of `forward(input_ids, decoder_input_ids)` only batches words of
+    /// the same token count
+    fn accepts_mask(model: &tch::CModule, device: tch::Device) -> bool {
+        let _g = tch::no_grad_guard();
+        let input = tch::Tensor::from_slice(&[BOS_TOKEN_ID as i64, EOS_TOKEN_ID as i64])
+            .view([1, 2])
+            .to_device(device);
+        let mask = input.ones_like();
+        let decoder_input = tch::Tensor::from_slice(&[DECODER_START_TOKEN_ID as i64])
+            .view([1, 1])
+            .to_device(device);
+        model.forward_ts(&[&input, &mask, &decoder_input]).is_ok()
+    }
+
     pub fn get_phoneme(&self, text: &str) -> Result<String> {
-        let c = self.tokenizer.encode(text, true)?;
-        let input_ids = c.get_ids().iter().map(|x| *x as i64).collect::<Vec<i64>>();
-        let mut decoder_input_ids = vec![DECODER_START_TOKEN_ID as i64];
+        Ok(self.get_phonemes(&[text])?.remove(0))
+    }
 
-        for _ in 0..50 {
-            let input = tch::Tensor::from_slice(&input_ids)
-                .to_device(self.device)
-                .unsqueeze(0);
-            let decoder_input = tch::Tensor::from_slice(&decoder_input_ids)
-                .to_device(self.device)
-                .unsqueeze(0);
+    /// Phonemes of each word, cached words are not decoded again and the rest are decoded
+    /// in batches
+    pub fn get_phonemes(&self, words: &[&str]) -> Result<Vec<String>> {
+        let mut found = HashMap::new();
+        {
+            let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            for w in words {
+                if !found.contains_key(w)
+                    && let Some(phonemes) = cache.get(*w)
+                {
+                    found.insert(*w, phonemes.clone());
+                }
+            }
+        }
 
-            let output = self.model.forward_ts(&[input, decoder_input])?;
+        let mut encoded = vec![];
+        let mut pending = HashSet::new();
+        for w in words {
+            if found.contains_key(w) || !pending.insert(*w) {
+                continue;
+            }
+            let c = self.tokenizer.encode(*w, true)?;
+            let ids = c.get_ids().iter().map(|x| *x as i64).collect::<Vec<i64>>();
+            encoded.push((*w, ids));
+        }
+        // words of a similar length pad less, without a mask the length must be the same
+        encoded.sort_by_key(|(_, ids)| ids.len());
+        let batches = encoded
+            .chunk_by(|(_, a), (_, b)| self.masked || a.len() == b.len())
+            .flat_map(|batch| batch.chunks(MAX_BATCH));
+        for batch in batches {
+            let (batch_words, input_ids): (Vec<_>, Vec<_>) = batch.iter().cloned().unzip();
+            debug!("g2p en decode: {:?}", batch_words);
+            let phonemes = self.decode(&input_ids)?;
+            let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            for (w, p) in batch_words.into_iter().zip(phonemes) {
+                cache.put(w.to_string(), p.clone());
+                found.insert(w, p);
+            }
+        }
 
-            let next_token_logits = output.get(0).get(-1);
+        Ok(words.iter().map(|w| found[w].clone()).collect())
+    }
 
-            let next_token_id = next_token_logits.argmax(0, true).int64_value(&[]);
-            decoder_input_ids.push(next_token_id);
-            if next_token_id == EOS_TOKEN_ID as i64 {
+    /// greedy decode, inputs are padded to the longest one and a finished row is fed pad tokens
+    fn decode(&self, input_ids: &[Vec<i64>]) -> Result<Vec<String>> {
+        let batch = input_ids.len();
+        let input_len = input_ids.iter().map(Vec::len).max().unwrap_or_default();
+        // about two phonemes for each letter is plenty, even for long words
+        let max_len = input_len * 3 + 8;
+        let (padded, mask): (Vec<_>, Vec<_>) = input_ids
+            .iter()
+            .flat_map(|ids| {
+                (0..input_len).map(|i| match ids.get(i) {
+                    Some(id) => (*id, 1i64),
+                    None => (PAD_TOKEN_ID as i64, 0),
+                })
+            })
+            .unzip();
+        let input = tch::Tensor::from_slice(&padded)
+            .view([batch as i64, input_len as i64])
+            .to_device(self.device);
+        let mask = tch::Tensor::from_slice(&mask)
+            .view([batch as i64, input_len as i64])
+            .to_device(self.device);
+        let mut decoder_input_ids = vec![vec![DECODER_START_TOKEN_ID as i64]; batch];
+        let mut done = vec![false; batch];
+
+        for _ in 0..max_len {
+            let step = decoder_input_ids[0].len();
+            let decoder_input = tch::Tensor::from_slice(&decoder_input_ids.concat())
+                .view([batch as i64, step as i64])
+                .to_device(self.device);
+
+            let output = if self.masked {
+                self.model.forward_ts(&[&input, &mask, &decoder_input])?
+            } else {
+                self.model.forward_ts(&[&input, &decoder_input])?
+            };
+
+            let next_token_ids = output.select(1, -1).argmax(-1, false);
+            for (i, ids) in decoder_input_ids.iter_mut().enumerate() {
+                if done[i] {
+                    ids.push(PAD_TOKEN_ID as i64);
+                    continue;
+                }
+                let next_token_id = next_token_ids.int64_value(&[i as i64]);
+                ids.push(next_token_id);
+                done[i] = next_token_id == EOS_TOKEN_ID as i64;
+            }
+            if done.iter().all(|d| *d) {
                 break;
             }
         }
+        if !done.iter().all(|d| *d) {
+            warn!("g2p en decode reached {} tokens", max_len);
+        }
 
-        let decoder_input_ids = decoder_input_ids
-            .iter()
-            .map(|x| *x as u32)
-            .collect::<Vec<u32>>();
-        Ok(self.tokenizer.decode(&decoder_input_ids, true)?)
+        decoder_input_ids
+            .into_iter()
+            .map(|ids| {
+                let ids = ids.iter().map(|x| *x as u32).collect::<Vec<u32>>();
+                Ok(self.tokenizer.decode(&ids, true)?)
+            })
+            .collect()
     }
 }
@@ -0,0 +1,296 @@
+//! Korean g2p, hangul is read by the standard pronunciation rules and then split into the
+//! compatibility jamo used by GPT-SoVITS v2.
+
+static INITIALS: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// compound vowels are divided like `divide_hangul` of GPT-SoVITS
+static MEDIALS: [&str; 21] = [
+    "ㅏ", "ㅐ", "ㅣㅏ", "ㅣㅐ", "ㅓ", "ㅔ", "ㅣㅓ", "ㅣㅔ", "ㅗ", "ㅗㅏ", "ㅗㅐ", "ㅗㅣ", "ㅣㅗ",
+    "ㅜ", "ㅜㅓ", "ㅜㅔ", "ㅜㅣ", "ㅣㅜ", "ㅡ", "ㅡㅣ", "ㅣ",
+];
+
+static FINALS: [&str; 28] = [
+    "", "ㄱ", "ㄲ", "ㄱㅅ", "ㄴ", "ㄴㅈ", "ㄴㅎ", "ㄷ", "ㄹ", "ㄹㄱ", "ㄹㅁ", "ㄹㅂ", "ㄹㅅ",
+    "ㄹㅌ", "ㄹㅍ", "ㄹㅎ", "ㅁ", "ㅂ", "ㅂㅅ", "ㅅ", "ㅆ", "ㅇ", "ㅈ", "ㅊ", "ㅋ", "ㅌ", "ㅍ",
+    "ㅎ",
+];
+
+/// bare compatibility jamo that are not in the symbols
+static JAMO_DIVIDED: [(char, &str); 24] = [
+    ('ㄳ', "ㄱㅅ"),
+    ('ㄵ', "ㄴㅈ"),
+    ('ㄶ', "ㄴㅎ"),
+    ('ㄺ', "ㄹㄱ"),
+    ('ㄻ', "ㄹㅁ"),
+    ('ㄼ', "ㄹㅂ"),
+    ('ㄽ', "ㄹㅅ"),
+    ('ㄾ', "ㄹㅌ"),
+    ('ㄿ', "ㄹㅍ"),
+    ('ㅀ', "ㄹㅎ"),
+    ('ㅄ', "ㅂㅅ"),
+    ('ㅘ', "ㅗㅏ"),
+    ('ㅙ', "ㅗㅐ"),
+    ('ㅚ', "ㅗㅣ"),
+    ('ㅝ', "ㅜㅓ"),
+    ('ㅞ', "ㅜㅔ"),
+    ('ㅟ', "ㅜㅣ"),
+    ('ㅢ', "ㅡㅣ"),
+    ('ㅑ', "ㅣㅏ"),
+    ('ㅒ', "ㅣㅐ"),
+    ('ㅕ', "ㅣㅓ"),
+    ('ㅖ', "ㅣㅔ"),
+    ('ㅛ', "ㅣㅗ"),
+    ('ㅠ', "ㅣㅜ"),
+];
+
+static LATIN_TO_HANGUL: [&str; 26] = [
+    "에이",
+    "비",
+    "시",
+    "디",
+    "이",
+    "에프",
+    "지",
+    "에이치",
+    "아이",
+    "제이",
+    "케이",
+    "엘",
+    "엠",
+    "엔",
+    "오",
+    "피",
+    "큐",
+    "아르",
+    "에스",
+    "티",
+    "유",
+    "브이",
+    "더블유",
+    "엑스",
+    "와이",
+    "제트",
+];
+
+const MEDIAL_I: usize = 20;
+
+#[derive(Debug, Clone)]
+struct Syllable {
+    initial: char,
+    medial: usize,
+    coda: Vec<char>,
+}
+
+impl Syllable {
+    fn new(c: char) -> Option<Self> {
+        let code = (c as u32).checked_sub(0xac00).filter(|c| *c < 11172)? as usize;
+        Some(Self {
+            initial: INITIALS[code / 588],
+            medial: code % 588 / 28,
+            coda: FINALS[code % 28].chars().collect(),
+        })
+    }
+}
+
+fn aspirate(c: char) -> char {
+    match c {
+        'ㄱ' | 'ㄲ' | 'ㅋ' => 'ㅋ',
+        'ㄷ' | 'ㅅ' | 'ㅆ' | 'ㅌ' => 'ㅌ',
+        'ㅈ' | 'ㅊ' => 'ㅊ',
+        'ㅂ' | 'ㅍ' => 'ㅍ',
+        c => c,
+    }
+}
+
+/// 음절의 끝소리 규칙, a coda is read as one of ㄱ ㄴ ㄷ ㄹ ㅁ ㅂ ㅇ
+fn neutralize(coda: &mut Vec<char>) {
+    let c = match coda.as_slice() {
+        [] => return,
+        ['ㄹ', 'ㄱ'] => 'ㄱ',
+        ['ㄹ', 'ㅁ'] => 'ㅁ',
+        ['ㄹ', 'ㅍ'] => 'ㅂ',
+        [c, ..] => *c,
+    };
+    let c = match c {
+        'ㄲ' | 'ㅋ' => 'ㄱ',
+        'ㅅ' | 'ㅆ' | 'ㅈ' | 'ㅊ' | 'ㅌ' | 'ㅎ' => 'ㄷ',
+        'ㅍ' => 'ㅂ',
+        c => c,
+    };
+    *coda = vec![c];
+}
+
+fn boundary(a: &mut Syllable, b: &mut Syllable) {
+    // ㅎ at the end merges into the next consonant or drops before a vowel
+    if a.coda.last() == Some(&'ㅎ') {
+        match b.initial {
+            'ㄱ' | 'ㄷ' | 'ㅈ' => {
+                a.coda.pop();
+                b.initial = aspirate(b.initial);
+            }
+            'ㅅ' => {
+                a.coda.pop();
+                b.initial = 'ㅆ';
+            }
+            'ㅇ' => {
+                a.coda.pop();
+            }
+            'ㄴ' => {
+                a.coda.pop();
+                if a.coda.is_empty() {
+                    a.coda.push('ㄴ');
+                }
+            }
+            _ => {}
+        }
+    }
+    // ㄱ ㄷ ㅂ ㅈ before ㅎ become aspirated, 굳히다 is read 구치다
+    if b.initial == 'ㅎ'
+        && let Some(&c) = a.coda.last()
+        && matches!(
+            c,
+            'ㄱ' | 'ㄲ' | 'ㅋ' | 'ㄷ' | 'ㅅ' | 'ㅆ' | 'ㅌ' | 'ㅈ' | 'ㅊ' | 'ㅂ' | 'ㅍ'
+        )
+    {
+        a.coda.pop();
+        b.initial = if c == 'ㄷ' && b.medial == MEDIAL_I {
+            'ㅊ'
+        } else {
+            aspirate(c)
+        };
+    }
+    // 연음, the coda moves to the empty initial
+    if b.initial == 'ㅇ'
+        && let Some(c) = a.coda.pop()
+    {
+        if c == 'ㅇ' {
+            a.coda.push(c);
+        } else {
+            b.initial = match c {
+                'ㄷ' if b.medial == MEDIAL_I => 'ㅈ',
+                'ㅌ' if b.medial == MEDIAL_I => 'ㅊ',
+                'ㅅ' if !a.coda.is_empty() => 'ㅆ',
+                c => c,
+            };
+        }
+    }
+    neutralize(&mut a.coda);
+
+    // 유음화 and 비음화
+    match (a.coda.first().copied(), b.initial) {
+        (Some('ㄴ'), 'ㄹ') => a.coda[0] = 'ㄹ',
+        (Some('ㄹ'), 'ㄴ') => b.initial = 'ㄹ',
+        (Some('ㄱ' | 'ㄷ' | 'ㅁ' | 'ㅂ' | 'ㅇ'), 'ㄹ') => b.initial = 'ㄴ',
+        _ => {}
+    }
+    if matches!(b.initial, 'ㄴ' | 'ㅁ')
+        && let Some(c) = a.coda.first_mut()
+    {
+        *c = match *c {
+            'ㄱ' => 'ㅇ',
+            'ㄷ' => 'ㄴ',
+            'ㅂ' => 'ㅁ',
+            c => c,
+        };
+    }
+
+    // 경음화
+    if matches!(a.coda.first(), Some('ㄱ' | 'ㄷ' | 'ㅂ')) {
+        b.initial = match b.initial {
+            'ㄱ' => 'ㄲ',
+            'ㄷ' => 'ㄸ',
+            'ㅂ' => 'ㅃ',
+            'ㅅ' => 'ㅆ',
+            'ㅈ' => 'ㅉ',
+            c => c,
+        };
+    }
+}
+
+fn push_word(word: &mut Vec<Syllable>, phones: &mut Vec<String>) {
+    for i in 1..word.len() {
+        let (a, b) = word.split_at_mut(i);
+        boundary(&mut a[i - 1], &mut b[0]);
+    }
+    if let Some(last) = word.last_mut() {
+        neutralize(&mut last.coda);
+    }
+    for s in word.drain(..) {
+        phones.push(s.initial.to_string());
+        phones.extend(MEDIALS[s.medial].chars().map(String::from));
+        phones.extend(s.coda.into_iter().map(String::from));
+    }
+}
+
+pub fn g2p(text: &str) -> Vec<String> {
+    let mut phones = vec![];
+    let mut word = vec![];
+    for c in latin_to_hangul(text).chars() {
+        if let Some(s) = Syllable::new(c) {
+            word.push(s);
+            continue;
+        }
+        push_word(&mut word, &mut phones);
+        if let Some((_, divided)) = JAMO_DIVIDED.iter().find(|(j, _)| *j == c) {
+            phones.extend(divided.chars().map(String::from));
+        } else if c == ' ' && phones.last().is_some_and(|p| p == " ") {
+            continue;
+        } else {
+            phones.push(c.to_string());
+        }
+    }
+    push_word(&mut word, &mut phones);
+    phones
+}
+
+/// latin letters are read by their korean names
+fn latin_to_hangul(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_alphabetic() {
+            s.push_str(LATIN_TO_HANGUL[(c.to_ascii_lowercase() as u8 - b'a') as usize]);
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the jamo of `text` without any pronunciation rule
+    fn jamo(text: &str) -> Vec<String> {
+        let mut phones = vec![];
+        for s in text.chars().filter_map(Syllable::new) {
+            phones.push(s.initial.to_string());
+            phones.extend(MEDIALS[s.medial].chars().map(String::from));
+            phones.extend(s.coda.into_iter().map(String::from));
+        }
+        phones
+    }
+
+    #[test]
+    fn rules() {
+        // 비음화, 유음화, 경음화 and 격음화
+        assert_eq!(g2p("국물"), jamo("궁물"));
+        assert_eq!(g2p("신라"), jamo("실라"));
+        assert_eq!(g2p("먹다"), jamo("먹따"));
+        assert_eq!(g2p("좋고"), jamo("조코"));
+        // 연음
+        assert_eq!(g2p("음악"), jamo("으막"));
+    }
+
+    #[test]
+    fn words_and_letters() {
+        assert_eq!(
+            g2p("한 명"),
+            [jamo("한"), vec![" ".to_string()], jamo("명")].concat()
+        );
+        assert_eq!(g2p("TV"), jamo("티브이"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Cantonese g2p, chars are read as jyutping from the bundled dict and split into the
+//! `Y` prefixed initials and finals used by GPT-SoVITS v2.
+
+use super::dict::{self, Dicts};
+use crate::symbols::SYMBOLS;
+use tracing::warn;
+
+/// same order as `INITIALS` of GPT-SoVITS, the first match wins
+static INITIALS: [&str; 43] = [
+    "aa", "aai", "aak", "aap", "aat", "aau", "ai", "au", "ap", "at", "ak", "a", "p", "b", "e",
+    "ts", "t", "dz", "d", "kw", "k", "gw", "g", "f", "h", "l", "m", "ng", "n", "s", "y", "w", "c",
+    "z", "j", "ong", "on", "ou", "oi", "ok", "o", "uk", "ung",
+];
+
+/// gwong2 -> (Ygw, Yong2), aa3 -> (Yaa, Ya3), ng5 -> (Yng, Yg5)
+fn split_jyutping(syllable: &str) -> Option<(String, String)> {
+    let (syllable, tone) = match syllable.char_indices().last() {
+        Some((i, t)) if t.is_ascii_digit() => (&syllable[..i], &syllable[i..]),
+        _ => (syllable, ""),
+    };
+    let (initial, final_) = if let Some(rest) = syllable.strip_prefix("nga") {
+        ("ng", format!("a{rest}"))
+    } else {
+        let initial = INITIALS.iter().find(|i| syllable.starts_with(*i))?;
+        let rest = &syllable[initial.len()..];
+        let final_ = if rest.is_empty() {
+            &initial[initial.len() - 1..]
+        } else {
+            rest
+        };
+        (*initial, final_.to_string())
+    };
+    Some((format!("Y{initial}"), format!("Y{final_}{tone}")))
+}
+
+/// both halves of the syllable are in the symbol table
+pub(super) fn is_jyutping(syllable: &str) -> bool {
+    split_jyutping(syllable)
+        .is_some_and(|(i, f)| SYMBOLS.contains_key(&i) && SYMBOLS.contains_key(&f))
+}
+
+fn push_jyutping(syllable: &str, phones: &mut Vec<String>) {
+    match split_jyutping(syllable) {
+        Some((initial, final_)) => {
+            phones.push(initial);
+            phones.push(final_);
+        }
+        None => warn!("unknown jyutping: {}", syllable),
+    }
+}
+
+/// longest match over the dict, a word gives its jyutping and a char out of the dict gives
+/// itself, each with the byte offset where it starts
+fn segment<'a>(text: &str, dicts: &'a Dicts) -> Vec<(usize, Result<&'a [&'static str], char>)> {
+    // byte offsets of the chars and the end, so the candidates are slices of `text`
+    let bounds = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .collect::<Vec<_>>();
+    let chars = bounds.len() - 1;
+    let mut words = vec![];
+    let mut i = 0;
+    while i < chars {
+        // 銀行 is read ngan4 hong4 while 行 alone is hang4
+        let found = (1..=dicts.yue_max_len().min(chars - i))
+            .rev()
+            .find_map(|n| {
+                let word = &text[bounds[i]..bounds[i + n]];
+                dicts.yue_word(word).map(|jyutping| (n, jyutping))
+            });
+        match found {
+            Some((n, jyutping)) => {
+                words.push((bounds[i], Ok(jyutping)));
+                i += n;
+            }
+            None => {
+                let c = text[bounds[i]..].chars().next().unwrap();
+                words.push((bounds[i], Err(c)));
+                i += 1;
+            }
+        }
+    }
+    words
+}
+
+/// the chars of `text` that are not in the dict, with their byte offsets
+pub fn unknown(text: &str, dicts: &Dicts) -> Vec<(usize, char)> {
+    segment(text, dicts)
+        .into_iter()
+        .filter_map(|(i, word)| word.err().map(|c| (i, c)))
+        .collect()
+}
+
+pub fn g2p(text: &str) -> Vec<String> {
+    let dicts = dict::snapshot();
+    let mut phones = vec![];
+    for (_, word) in segment(text, &dicts) {
+        match word {
+            Ok(jyutping) => {
+                for syllable in jyutping {
+                    push_jyutping(syllable, &mut phones);
+                }
+            }
+            Err(c @ (',' | '.' | '!' | '?' | '…' | '-' | '\'' | '[' | ']')) => {
+                phones.push(c.to_string())
+            }
+            Err(' ') => {}
+            Err(c) => warn!("skip cantonese char: {:?}", c),
+        }
+    }
+    phones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jyutping() {
+        assert_eq!(
+            split_jyutping("gwong2"),
+            Some(("Ygw".to_string(), "Yong2".to_string()))
+        );
+        assert_eq!(
+            split_jyutping("ng5"),
+            Some(("Yng".to_string(), "Yg5".to_string()))
+        );
+        assert!(is_jyutping("jat1"));
+        assert!(!is_jyutping("qqq3"));
+    }
+
+    #[test]
+    fn longest_match() {
+        assert_eq!(g2p("銀行"), ["Yng", "Yan4", "Yh", "Yong4"]);
+        assert_eq!(g2p("行"), ["Yh", "Yaang4"]);
+        assert_eq!(
+            g2p("一齊, 行"),
+            ["Yj", "Yat1", "Yc", "Yai4", ",", "Yh", "Yaang4"]
+        );
+    }
+
+    #[test]
+    fn unknown_chars() {
+        let dicts = dict::snapshot();
+        assert_eq!(unknown("一齊", &dicts), []);
+        assert_eq!(unknown("一龘齊", &dicts), [(3, '龘')]);
+        assert_eq!(g2p("一龘"), ["Yj", "Yat1"]);
+    }
+}
@@ -6,6 +6,14 @@ static POLY_CHARS_DIST_STR: &str = include_str!("../../resource/g2pw/dict_poly_c
 static LABELS: &str = include_str!("../../resource/g2pw/dict_poly_index_list.json");
 pub(crate) static G2PW_TOKENIZER: &str = include_str!("../../resource/g2pw_tokenizer.json");
 
+/// bert takes 512 tokens, with '[CLS]' and '[SEP]'
+const MAX_CHARS: usize = 510;
+/// polyphonic chars in one forward, each of them repeats the whole sentence
+const MAX_BATCH: usize = 32;
+static SPLIT_PUNCTUATION: [char; 12] = [
+    '，', '。', '！', '？', '；', '、', ',', '.', '!', '?', ';', ' ',
+];
+
 fn load_mono_chars() -> HashMap<char, MonoChar> {
     if let Ok(dir) = std::env::var("G2PW_DIST_DIR") {
         let s = std::fs::read_to_string(format!("{}/dict_mono_chars.json", dir))
@@ -75,6 +83,26 @@ pub fn str_is_chinese(s: &str) -> bool {
     r
 }
 
+/// the bundled label of a pinyin, so user rules can live as long as the model output
+pub fn pinyin_label(c: char, pinyin: &str) -> Option<&'static str> {
+    let poly = DICT_POLY_CHARS
+        .get(&c)
+        .and_then(|poly| poly.phones.iter().find(|(p, _)| p == pinyin))
+        .map(|(p, _)| p.as_str());
+    let mono = || {
+        DICT_MONO_CHARS
+            .get(&c)
+            .filter(|mono| mono.phone == pinyin)
+            .map(|mono| mono.phone.as_str())
+    };
+    poly.or_else(mono).or_else(|| {
+        POLY_LABLES
+            .iter()
+            .find(|p| *p == pinyin)
+            .map(String::as_str)
+    })
+}
+
 impl G2PWConverter {
     pub fn empty() -> Self {
         Self {
@@ -131,29 +159,34 @@ impl G2PWConverter {
     }
 
     fn ml_get_pinyin(&self, text: &str) -> Result<Vec<G2PWOut>> {
+        let mut pre_data = Vec::with_capacity(text.len());
+        for chunk in split_long(text, MAX_CHARS) {
+            pre_data.extend(self.ml_get_chunk_pinyin(chunk)?);
+        }
+        Ok(pre_data)
+    }
+
+    fn ml_get_chunk_pinyin(&self, text: &str) -> Result<Vec<G2PWOut>> {
         let c = self.tokenizers.as_ref().unwrap().encode(text, true)?;
         let input_ids = c.get_ids().iter().map(|x| *x as i64).collect::<Vec<i64>>();
         let token_type_ids = vec![0i64; input_ids.len()];
         let attention_mask = vec![1i64; input_ids.len()];
 
-        let mut phoneme_masks = vec![];
         let mut pre_data = vec![];
-        let mut query_id = vec![];
-        let mut chars_id = vec![];
+        // (position_id, char_id, phoneme_mask) of each polyphonic char
+        let mut queries = vec![];
 
         for (i, c) in text.chars().enumerate() {
             if let Some(mono) = DICT_MONO_CHARS.get(&c) {
                 pre_data.push(G2PWOut::Pinyin(&mono.phone));
             } else if let Some(poly) = DICT_POLY_CHARS.get(&c) {
                 pre_data.push(G2PWOut::Pinyin(""));
-                // 这个位置是 tokens 的位置，它的前后添加了 '[CLS]' 和 '[SEP]' 两个特殊字符
-                query_id.push(i + 1);
-                chars_id.push(poly.index);
                 let mut phoneme_mask = vec![0f32; POLY_LABLES.len()];
                 for (_, i) in &poly.phones {
                     phoneme_mask[*i] = 1.0;
                 }
-                phoneme_masks.push(phoneme_mask);
+                // 这个位置是 tokens 的位置，它的前后添加了 '[CLS]' 和 '[SEP]' 两个特殊字符
+                queries.push((i as i64 + 1, poly.index as i64, phoneme_mask));
             } else {
                 pre_data.push(G2PWOut::RawChar(c));
             }
@@ -169,34 +202,57 @@ impl G2PWConverter {
             .unsqueeze(0)
             .to_device(self.device);
 
-        for ((position_id, phoneme_mask), char_id) in query_id
-            .iter()
-            .zip(phoneme_masks.iter())
-            .zip(chars_id.iter())
-        {
-            let phoneme_mask = tch::Tensor::from_slice(phoneme_mask)
-                .unsqueeze(0)
+        // every polyphonic char is a row of one forward, with the sentence repeated
+        for batch in queries.chunks(MAX_BATCH) {
+            let n = batch.len() as i64;
+            let position_ids = batch.iter().map(|q| q.0).collect::<Vec<_>>();
+            let char_ids = batch.iter().map(|q| q.1).collect::<Vec<_>>();
+            let phoneme_masks = batch
+                .iter()
+                .flat_map(|q| q.2.iter().copied())
+                .collect::<Vec<_>>();
+
+            let phoneme_mask = tch::Tensor::from_slice(&phoneme_masks)
+                .view([n, POLY_LABLES.len() as i64])
                 .to_device(self.device);
-            let position_id_t =
-                tch::Tensor::from_slice(&[*position_id as i64]).to_device(self.device);
-            let char_id = tch::Tensor::from_slice(&[*char_id as i64]).to_device(self.device);
+            let position_id_t = tch::Tensor::from_slice(&position_ids).to_device(self.device);
+            let char_id = tch::Tensor::from_slice(&char_ids).to_device(self.device);
 
             let probs = tch::no_grad(|| {
                 self.model.as_ref().unwrap().forward_ts(&[
-                    &input_ids,
-                    &token_type_ids,
-                    &attention_mask,
+                    &input_ids.repeat([n, 1]),
+                    &token_type_ids.repeat([n, 1]),
+                    &attention_mask.repeat([n, 1]),
                     &phoneme_mask,
                     &char_id,
                     &position_id_t,
                 ])
             })?;
 
-            let i = probs.argmax(-1, false).int64_value(&[]);
-
-            pre_data[*position_id - 1] = G2PWOut::Pinyin(&POLY_LABLES[i as usize]);
+            let labels = probs.argmax(-1, false);
+            for (k, position_id) in position_ids.iter().enumerate() {
+                let i = labels.int64_value(&[k as i64]);
+                pre_data[*position_id as usize - 1] = G2PWOut::Pinyin(&POLY_LABLES[i as usize]);
+            }
         }
 
         Ok(pre_data)
     }
 }
+
+/// Split before `max` chars, at the last punctuation when there is one
+fn split_long(text: &str, max: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = text;
+    while let Some((end, _)) = rest.char_indices().nth(max) {
+        let head = &rest[..end];
+        let at = head
+            .char_indices()
+            .rfind(|(_, c)| SPLIT_PUNCTUATION.contains(c))
+            .map_or(end, |(i, c)| i + c.len_utf8());
+        chunks.push(&rest[..at]);
+        rest = &rest[at..];
+    }
+    chunks.push(rest);
+    chunks
+}
@@ -0,0 +1,98 @@
+//! Inline pronunciation in the request text. `行{hang2}` reads the chinese chars before the
+//! braces with the given pinyin, one per char, so `银行{yin2 hang2}` pins both chars.
+//! `{en:HH AH0 L OW1}` reads the ARPAbet as is, after a word like `live{en:L IH1 V}` it
+//! replaces that word. A span that does not validate is read as plain text.
+
+use super::{g2pw, split_zh_ph};
+use crate::symbols::SYMBOLS;
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::warn;
+
+lazy_static! {
+    static ref RE_MARKUP: Regex =
+        Regex::new(r"(\p{Han}*)\{\s*([a-zA-Z0-9 ]+?)\s*\}|([A-Za-z']*)\{\s*en:([^{}]*)\}").unwrap();
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Span<'a> {
+    Text(&'a str),
+    /// chinese chars and their pinyin
+    Zh(&'a str, Vec<&'static str>),
+    /// an english word, may be empty, and its ARPAbet
+    En(&'a str, Vec<&'static str>),
+}
+
+fn symbol(p: &str) -> Option<&'static str> {
+    SYMBOLS.get_key_value(p).map(|(k, _)| k.as_str())
+}
+
+fn pinyin(c: char, p: &str) -> Option<&'static str> {
+    let p = g2pw::pinyin_label(c, &p.to_lowercase())?;
+    let (s, y) = split_zh_ph(p);
+    (symbol(s).is_some() && symbol(y).is_some()).then_some(p)
+}
+
+fn arpabet(phones: &str) -> Option<Vec<&'static str>> {
+    let phones = phones
+        .split_whitespace()
+        .map(|p| symbol(&p.to_ascii_uppercase()))
+        .collect::<Option<Vec<_>>>()?;
+    (!phones.is_empty()).then_some(phones)
+}
+
+/// Split `text` into plain text and pinned spans
+pub fn parse(text: &str) -> Vec<Span<'_>> {
+    if !text.contains('{') {
+        return vec![Span::Text(text)];
+    }
+    let mut spans = vec![];
+    let mut last = 0;
+    for caps in RE_MARKUP.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        let (word, span) = match caps.get(1) {
+            Some(word) => {
+                let pinyin_list = caps[2].split_whitespace().collect::<Vec<_>>();
+                let start = pinyin_list.len().checked_sub(1).and_then(|n| {
+                    word.as_str()
+                        .char_indices()
+                        .rev()
+                        .nth(n)
+                        .map(|(i, _)| word.start() + i)
+                });
+                let span = start.and_then(|start| {
+                    let chars = &text[start..word.end()];
+                    chars
+                        .chars()
+                        .zip(&pinyin_list)
+                        .map(|(c, p)| pinyin(c, p))
+                        .collect::<Option<Vec<_>>>()
+                        .map(|pinyin| (start, Span::Zh(chars, pinyin)))
+                });
+                (word, span)
+            }
+            None => {
+                let word = caps.get(3).unwrap();
+                let span =
+                    arpabet(&caps[4]).map(|phones| (word.start(), Span::En(word.as_str(), phones)));
+                (word, span)
+            }
+        };
+        let (start, span) = match span {
+            Some((start, span)) => (start, Some(span)),
+            None => {
+                warn!("invalid pronunciation, read as text: {}", m.as_str());
+                (m.end(), None)
+            }
+        };
+        if last < start {
+            spans.push(Span::Text(&text[last..start]));
+        }
+        spans.extend(span);
+        last = m.end();
+    }
+    if last < text.len() {
+        spans.push(Span::Text(&text[last..]));
+    }
+    spans
+}
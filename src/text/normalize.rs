@@ -0,0 +1,1423 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Whether the span `start..end` of `text` is surrounded by English words.
+///
+/// The nearest letter before the span wins, then the nearest letter after it.
+/// A span without any letters around it is English only if the whole text is ASCII.
+pub(crate) fn is_en_context(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().rev().find(|c| c.is_alphabetic());
+    let after = text[end..].chars().find(|c| c.is_alphabetic());
+    match before.or(after) {
+        Some(c) => c.is_ascii_alphabetic(),
+        None => text.is_ascii(),
+    }
+}
+
+/// What to do with emoji and other pictographic symbols that no g2p can read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolPolicy {
+    /// leave them in the text, they are skipped later
+    Keep,
+    #[default]
+    Drop,
+    /// read the english name of an emoji, "👍" -> "thumbs up"
+    Name,
+}
+
+/// The unicode stage that runs before segmentation
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct NormalizeConfig {
+    /// NFKC, also folds compatibility chars like "①" and "㎏"
+    pub nfkc: bool,
+    /// "café" -> "cafe", only latin letters are touched
+    pub transliterate: bool,
+    /// fullwidth ascii to ascii and halfwidth katakana to fullwidth
+    pub fold_width: bool,
+    pub symbols: SymbolPolicy,
+    /// user replacements, applied before anything else, like "℃" -> "摄氏度"
+    pub symbol_table: HashMap<String, String>,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        Self {
+            nfkc: false,
+            transliterate: true,
+            fold_width: true,
+            symbols: SymbolPolicy::default(),
+            symbol_table: HashMap::new(),
+        }
+    }
+}
+
+pub mod unicode {
+    use super::*;
+    use unicode_normalization::UnicodeNormalization;
+    use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+    /// latin letters without a canonical decomposition
+    static LATIN_LETTERS: [(char, &str); 18] = [
+        ('ß', "ss"),
+        ('ẞ', "SS"),
+        ('æ', "ae"),
+        ('Æ', "AE"),
+        ('œ', "oe"),
+        ('Œ', "OE"),
+        ('ø', "o"),
+        ('Ø', "O"),
+        ('ł', "l"),
+        ('Ł', "L"),
+        ('đ', "d"),
+        ('Đ', "D"),
+        ('ð', "d"),
+        ('Ð', "D"),
+        ('þ', "th"),
+        ('Þ', "Th"),
+        ('ı', "i"),
+        ('ĸ', "k"),
+    ];
+
+    fn is_latin(c: char) -> bool {
+        matches!(c, '\u{00c0}'..='\u{024f}' | '\u{1e00}'..='\u{1eff}')
+    }
+
+    fn is_halfwidth_kana(c: char) -> bool {
+        matches!(c, '\u{ff61}'..='\u{ff9f}')
+    }
+
+    /// emoji and pictographs, the emoji of Extended_Pictographic below U+1F000. Arrows and
+    /// the dingbat numbers like "❶" and "➀" are kept for the later steps.
+    fn is_pictographic(c: char) -> bool {
+        matches!(c,
+            '\u{231a}'..='\u{231b}' | '\u{2328}' | '\u{23cf}' |   // watch, keyboard, eject
+            '\u{23e9}'..='\u{23f3}' | '\u{23f8}'..='\u{23fa}' |   // media buttons
+            '\u{25aa}'..='\u{25ab}' | '\u{25b6}' | '\u{25c0}' |   // emoji squares and triangles
+            '\u{25fb}'..='\u{25fe}' |
+            '\u{2600}'..='\u{2775}' |                         // Miscellaneous Symbols and Dingbats
+            '\u{2794}'..='\u{27bf}' |
+            '\u{2b1b}'..='\u{2b1c}' | '\u{2b50}' | '\u{2b55}' |   // large squares, star, circle
+            '\u{1f000}'..='\u{1faff}'                         // Emoji and pictographs
+        )
+    }
+
+    /// modifiers that belong to the emoji before them
+    fn is_emoji_modifier(c: char) -> bool {
+        matches!(c,
+            '\u{fe0e}' | '\u{fe0f}' | '\u{20e3}' |
+            '\u{1f3fb}'..='\u{1f3ff}' |                       // skin tones
+            '\u{e0020}'..='\u{e007f}'                         // tags
+        )
+    }
+
+    fn fold_width(text: &str) -> String {
+        let mut s = String::with_capacity(text.len());
+        let mut kana = String::new();
+        for c in text.chars() {
+            if is_halfwidth_kana(c) {
+                kana.push(c);
+                continue;
+            }
+            if !kana.is_empty() {
+                // ｶﾞ -> ガ, the voiced mark composes with the kana before it
+                s.extend(kana.nfkc());
+                kana.clear();
+            }
+            match c {
+                '\u{ff01}'..='\u{ff5e}' => {
+                    s.push(char::from_u32(c as u32 - 0xfee0).unwrap_or(c));
+                }
+                '\u{3000}' => s.push(' '),
+                c => s.push(c),
+            }
+        }
+        s.extend(kana.nfkc());
+        s
+    }
+
+    fn transliterate(text: &str) -> String {
+        let mut s = String::with_capacity(text.len());
+        for c in text.chars() {
+            if !is_latin(c) {
+                s.push(c);
+            } else if let Some((_, ascii)) = LATIN_LETTERS.iter().find(|(l, _)| *l == c) {
+                s.push_str(ascii);
+            } else {
+                decompose_canonical(c, |d| {
+                    if !is_combining_mark(d) {
+                        s.push(d);
+                    }
+                });
+            }
+        }
+        s
+    }
+
+    /// an emoji with its modifiers and the ones joined to it by ZWJ
+    fn emoji_len(text: &str) -> usize {
+        let mut chars = text.char_indices().skip(1).peekable();
+        let mut end = text.chars().next().map_or(0, char::len_utf8);
+        while let Some((i, c)) = chars.next() {
+            if is_emoji_modifier(c) {
+                end = i + c.len_utf8();
+            } else if c == '\u{200d}'
+                && let Some((j, n)) = chars.next()
+            {
+                end = j + n.len_utf8();
+            } else {
+                break;
+            }
+        }
+        end
+    }
+
+    fn apply_symbol_policy(text: &str, policy: SymbolPolicy) -> String {
+        let mut s = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if !is_pictographic(c) {
+                s.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let len = emoji_len(rest);
+            let emoji = &rest[..len];
+            rest = &rest[len..];
+            if policy == SymbolPolicy::Name
+                && let Some(e) = emojis::get(emoji).or_else(|| emojis::get(&emoji[..c.len_utf8()]))
+            {
+                s.push(' ');
+                s.push_str(e.name());
+                s.push(' ');
+            }
+        }
+        s
+    }
+
+    /// Run the configured steps in order: the user table, NFKC, width folding,
+    /// transliteration and the symbol policy.
+    pub fn normalize<'a>(text: &'a str, config: &NormalizeConfig) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        let mut table = config.symbol_table.iter().collect::<Vec<_>>();
+        table.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        for (from, to) in table {
+            if text.contains(from.as_str()) {
+                text = Cow::Owned(text.replace(from.as_str(), to));
+            }
+        }
+        if config.nfkc {
+            text = Cow::Owned(text.nfkc().collect());
+        }
+        if config.fold_width
+            && text
+                .chars()
+                .any(|c| matches!(c, '\u{ff01}'..='\u{ff5e}' | '\u{3000}') || is_halfwidth_kana(c))
+        {
+            text = Cow::Owned(fold_width(&text));
+        }
+        if config.transliterate && text.chars().any(is_latin) {
+            text = Cow::Owned(transliterate(&text));
+        }
+        if config.symbols != SymbolPolicy::Keep && text.chars().any(is_pictographic) {
+            text = Cow::Owned(apply_symbol_policy(&text, config.symbols));
+        }
+        text
+    }
+}
+
+pub mod en {
+    use super::*;
+    use regex::Captures;
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Cond {
+        Always,
+        // only before a number, like "No. 5" or "Jan. 3"
+        BeforeDigit,
+        // a title, the following name keeps the sentence going
+        Title,
+    }
+
+    static ABBREVIATIONS: &[(&str, &str, Cond)] = &[
+        ("Mr", "Mister", Cond::Title),
+        ("Mrs", "Missus", Cond::Title),
+        ("Ms", "Miz", Cond::Title),
+        ("Dr", "Doctor", Cond::Title),
+        ("Prof", "Professor", Cond::Title),
+        ("Rev", "Reverend", Cond::Title),
+        ("Hon", "Honorable", Cond::Title),
+        ("Pres", "President", Cond::Title),
+        ("Gov", "Governor", Cond::Title),
+        ("Sen", "Senator", Cond::Title),
+        ("Rep", "Representative", Cond::Title),
+        ("Gen", "General", Cond::Title),
+        ("Col", "Colonel", Cond::Title),
+        ("Capt", "Captain", Cond::Title),
+        ("Lt", "Lieutenant", Cond::Title),
+        ("Sgt", "Sergeant", Cond::Title),
+        ("Mt", "Mount", Cond::Title),
+        ("Jr", "Junior", Cond::Always),
+        ("Sr", "Senior", Cond::Always),
+        ("Ave", "Avenue", Cond::Always),
+        ("Blvd", "Boulevard", Cond::Always),
+        ("Rd", "Road", Cond::Always),
+        ("Co", "Company", Cond::Always),
+        ("Corp", "Corporation", Cond::Always),
+        ("Inc", "Incorporated", Cond::Always),
+        ("Ltd", "Limited", Cond::Always),
+        ("Dept", "Department", Cond::Always),
+        ("Univ", "University", Cond::Always),
+        ("approx", "approximately", Cond::Always),
+        ("est", "established", Cond::BeforeDigit),
+        ("No", "number", Cond::BeforeDigit),
+        ("no", "number", Cond::BeforeDigit),
+        ("Vol", "volume", Cond::BeforeDigit),
+        ("vol", "volume", Cond::BeforeDigit),
+        ("Fig", "figure", Cond::BeforeDigit),
+        ("fig", "figure", Cond::BeforeDigit),
+        ("p", "page", Cond::BeforeDigit),
+        ("pp", "pages", Cond::BeforeDigit),
+        ("Jan", "January", Cond::BeforeDigit),
+        ("Feb", "February", Cond::BeforeDigit),
+        ("Mar", "March", Cond::BeforeDigit),
+        ("Apr", "April", Cond::BeforeDigit),
+        ("Jun", "June", Cond::BeforeDigit),
+        ("Jul", "July", Cond::BeforeDigit),
+        ("Aug", "August", Cond::BeforeDigit),
+        ("Sep", "September", Cond::BeforeDigit),
+        ("Sept", "September", Cond::BeforeDigit),
+        ("Oct", "October", Cond::BeforeDigit),
+        ("Nov", "November", Cond::BeforeDigit),
+        ("Dec", "December", Cond::BeforeDigit),
+    ];
+
+    static LATIN_ABBREVIATIONS: &[(&str, &str)] = &[
+        ("e.g.", "for example"),
+        ("E.g.", "for example"),
+        ("i.e.", "that is"),
+        ("I.e.", "that is"),
+        ("etc.", "et cetera"),
+        ("et al.", "and others"),
+        ("cf.", "compare"),
+        ("viz.", "namely"),
+        ("vs.", "versus"),
+        ("vs", "versus"),
+        ("ca.", "circa"),
+        ("a.k.a.", "also known as"),
+        ("N.B.", "note"),
+        ("P.S.", "postscript"),
+    ];
+
+    static MONTHS: &str =
+        "January|February|March|April|May|June|July|August|September|October|November|December";
+
+    lazy_static! {
+        static ref RE_ABBREVIATION: Regex = {
+            let words = ABBREVIATIONS
+                .iter()
+                .map(|(a, _, _)| regex::escape(a))
+                .collect::<Vec<_>>()
+                .join("|");
+            Regex::new(&format!(r"\b({words})\.(\s*)")).unwrap()
+        };
+        static ref RE_LATIN: Regex = {
+            let words = LATIN_ABBREVIATIONS
+                .iter()
+                .map(|(a, _)| regex::escape(a))
+                .collect::<Vec<_>>()
+                .join("|");
+            Regex::new(&format!(r"\b({words})(\s|$|,)")).unwrap()
+        };
+        static ref RE_ORDINAL: Regex = Regex::new(r"\b(\d+)(?i:(st|nd|rd|th))\b").unwrap();
+        static ref RE_DECADE: Regex = Regex::new(r"\b(1[1-9]|20)(\d0)'?s\b").unwrap();
+        static ref RE_YEAR: Regex = Regex::new(&format!(
+            r"(?i)\b(in|since|from|by|until|till|before|after|during|of|year|circa|around|{MONTHS})(\s+|\s+\d{{1,2}},\s*)(1[1-9]\d\d|20\d\d)\b"
+        ))
+        .unwrap();
+        static ref RE_CURRENCY: Regex = Regex::new(
+            r"([$€£¥])\s?(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d{1,2}))?(?:\s?(k|K|thousand|million|billion|trillion)\b)?"
+        )
+        .unwrap();
+        static ref RE_TIME: Regex = Regex::new(
+            r"\b(\d{1,2}):(\d{2})(?::(\d{2}))?(?:\s?([aApP])\.?[mM](\.?)(\s|$|[,;!?]))?"
+        )
+        .unwrap();
+        static ref RE_HOUR_AM_PM: Regex =
+            Regex::new(r"\b(\d{1,2})\s?([aApP])\.?[mM](\.?)(\s|$|[,;!?])").unwrap();
+    }
+
+    pub fn cardinal_to_words(n: u64) -> String {
+        num2en::str_to_words(&n.to_string())
+            .map(|s| s.replace('-', " "))
+            .unwrap_or_else(|_| n.to_string())
+    }
+
+    pub fn ordinal_to_words(n: u64) -> String {
+        let words = cardinal_to_words(n);
+        let (head, last) = match words.rsplit_once(' ') {
+            Some((head, last)) => (format!("{head} "), last),
+            None => (String::new(), words.as_str()),
+        };
+        let last = match last {
+            "one" => "first".to_string(),
+            "two" => "second".to_string(),
+            "three" => "third".to_string(),
+            "five" => "fifth".to_string(),
+            "eight" => "eighth".to_string(),
+            "nine" => "ninth".to_string(),
+            "twelve" => "twelfth".to_string(),
+            w if w.ends_with('y') => format!("{}ieth", &w[..w.len() - 1]),
+            w => format!("{w}th"),
+        };
+        format!("{head}{last}")
+    }
+
+    /// 1999 -> nineteen ninety nine, 2005 -> two thousand five, 1905 -> nineteen oh five
+    pub fn year_to_words(year: u64) -> String {
+        let (hi, lo) = (year / 100, year % 100);
+        if !(1000..10000).contains(&year) || (year % 1000 < 10 && hi % 10 == 0) {
+            return cardinal_to_words(year);
+        }
+        match lo {
+            0 => format!("{} hundred", cardinal_to_words(hi)),
+            1..10 => format!("{} oh {}", cardinal_to_words(hi), cardinal_to_words(lo)),
+            _ => format!("{} {}", cardinal_to_words(hi), cardinal_to_words(lo)),
+        }
+    }
+
+    fn digits_to_words(digits: &str) -> String {
+        digits
+            .chars()
+            .filter_map(|d| d.to_digit(10))
+            .map(|d| cardinal_to_words(d as u64))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn plural_to_words(words: String) -> String {
+        match words.strip_suffix('y') {
+            Some(w) => format!("{w}ies"),
+            None => format!("{words}s"),
+        }
+    }
+
+    fn parse_amount(s: &str) -> Option<u64> {
+        s.replace(',', "").parse().ok()
+    }
+
+    fn currency_names(
+        symbol: &str,
+    ) -> (
+        &'static str,
+        &'static str,
+        Option<(&'static str, &'static str)>,
+    ) {
+        match symbol {
+            "€" => ("euro", "euros", Some(("cent", "cents"))),
+            "£" => ("pound", "pounds", Some(("penny", "pence"))),
+            "¥" => ("yen", "yen", None),
+            _ => ("dollar", "dollars", Some(("cent", "cents"))),
+        }
+    }
+
+    fn expand_currency(text: &str, caps: &Captures) -> Option<String> {
+        let m = caps.get(0)?;
+        if !is_en_context(text, m.start(), m.end()) {
+            return None;
+        }
+        let (one, many, sub) = currency_names(&caps[1]);
+        let amount = parse_amount(&caps[2])?;
+        let cents = caps.get(3).map(|c| {
+            let c = c.as_str();
+            // "$4.5" means fifty cents
+            if c.len() == 1 {
+                c.parse::<u64>().unwrap_or(0) * 10
+            } else {
+                c.parse().unwrap_or(0)
+            }
+        });
+
+        if let Some(scale) = caps.get(4) {
+            let scale = match scale.as_str() {
+                "k" | "K" => "thousand",
+                s => s,
+            };
+            let number = match caps.get(3) {
+                Some(c) => format!(
+                    "{} point {}",
+                    cardinal_to_words(amount),
+                    digits_to_words(c.as_str())
+                ),
+                None => cardinal_to_words(amount),
+            };
+            return Some(format!("{number} {scale} {many}"));
+        }
+
+        let mut r = format!(
+            "{} {}",
+            cardinal_to_words(amount),
+            if amount == 1 { one } else { many }
+        );
+        match (cents, sub) {
+            (Some(c), Some((sub_one, sub_many))) if c > 0 => {
+                r.push_str(&format!(
+                    " and {} {}",
+                    cardinal_to_words(c),
+                    if c == 1 { sub_one } else { sub_many }
+                ));
+            }
+            _ => {}
+        }
+        Some(r)
+    }
+
+    pub fn clock_to_words(hour: u64, minute: u64) -> String {
+        match minute {
+            0 => cardinal_to_words(hour),
+            1..10 => format!(
+                "{} oh {}",
+                cardinal_to_words(hour),
+                cardinal_to_words(minute)
+            ),
+            _ => format!("{} {}", cardinal_to_words(hour), cardinal_to_words(minute)),
+        }
+    }
+
+    /// " AM", a trailing "a.m." that closes the sentence keeps its period
+    fn meridiem_to_words(letter: &str, dot: &str, tail: &str, rest: &str) -> String {
+        let closes = !dot.is_empty()
+            && (tail.contains('\n')
+                || rest
+                    .trim_start()
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_uppercase));
+        format!(
+            " {}M{}{}",
+            letter.to_ascii_uppercase(),
+            if closes { "." } else { "" },
+            tail
+        )
+    }
+
+    fn expand_time(text: &str, caps: &Captures) -> Option<String> {
+        let m = caps.get(0)?;
+        let hour: u64 = caps[1].parse().ok()?;
+        let minute: u64 = caps[2].parse().ok()?;
+        let second: Option<u64> = caps.get(3).and_then(|s| s.as_str().parse().ok());
+        if hour > 24
+            || minute > 59
+            || second.is_some_and(|s| s > 59)
+            || text[m.end()..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            return None;
+        }
+        if caps.get(4).is_none() && !is_en_context(text, m.start(), m.end()) {
+            return None;
+        }
+
+        let mut r = clock_to_words(hour, minute);
+        if let Some(second) = second {
+            r.push_str(&format!(" and {} seconds", cardinal_to_words(second)));
+        }
+        match caps.get(4) {
+            Some(letter) => r.push_str(&meridiem_to_words(
+                letter.as_str(),
+                &caps[5],
+                &caps[6],
+                &text[m.end()..],
+            )),
+            None if minute == 0 && second.is_none() => r.push_str(" o'clock"),
+            None => {}
+        }
+        Some(r)
+    }
+
+    fn expand_abbreviation(text: &str, caps: &Captures) -> String {
+        let m = caps.get(0).unwrap();
+        let abbr = &caps[1];
+        let space = &caps[2];
+        let (_, expansion, cond) = ABBREVIATIONS
+            .iter()
+            .find(|(a, _, _)| *a == abbr)
+            .copied()
+            .unwrap_or(("", "", Cond::Always));
+        let next = text[m.end()..].chars().next();
+
+        match cond {
+            Cond::BeforeDigit if !next.is_some_and(|c| c.is_ascii_digit()) => {
+                return m.as_str().to_string();
+            }
+            Cond::Title if space.is_empty() && next.is_some() => {
+                // something like "Dr.Smith" or a domain name, leave it alone
+                return m.as_str().to_string();
+            }
+            _ => {}
+        }
+        // the abbreviation also closed the sentence
+        let ends_sentence = cond == Cond::Always
+            && (next.is_none() || (space.contains('\n') || next.is_some_and(char::is_uppercase)));
+        if ends_sentence {
+            format!("{expansion}.{space}")
+        } else if space.is_empty() && next.is_some() {
+            format!("{expansion} ")
+        } else {
+            format!("{expansion}{space}")
+        }
+    }
+
+    fn expand_latin(caps: &Captures) -> String {
+        let abbr = &caps[1];
+        let tail = &caps[2];
+        let expansion = LATIN_ABBREVIATIONS
+            .iter()
+            .find(|(a, _)| *a == abbr)
+            .map(|(_, e)| *e)
+            .unwrap_or(abbr);
+        if tail.is_empty() && abbr.ends_with('.') {
+            format!("{expansion}.")
+        } else {
+            format!("{expansion}{tail}")
+        }
+    }
+
+    /// Rewrite abbreviations, ordinals, years, currency and clock times into plain English words.
+    ///
+    /// Currency and time are only rewritten in English context, see [`is_en_context`].
+    pub fn normalize(text: &str) -> Cow<'_, str> {
+        if !text
+            .chars()
+            .any(|c| c.is_ascii_alphanumeric() || "$€£¥".contains(c))
+        {
+            return Cow::Borrowed(text);
+        }
+
+        let text = RE_LATIN.replace_all(text, expand_latin);
+        let t = text.as_ref();
+        let text = RE_ABBREVIATION
+            .replace_all(t, |caps: &Captures| expand_abbreviation(t, caps))
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_CURRENCY
+            .replace_all(t, |caps: &Captures| {
+                expand_currency(t, caps).unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_TIME
+            .replace_all(t, |caps: &Captures| {
+                expand_time(t, caps).unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_HOUR_AM_PM
+            .replace_all(t, |caps: &Captures| {
+                let m = caps.get(0).unwrap();
+                match caps[1].parse() {
+                    Ok(hour) if hour <= 24 => format!(
+                        "{}{}",
+                        cardinal_to_words(hour),
+                        meridiem_to_words(&caps[2], &caps[3], &caps[4], &t[m.end()..])
+                    ),
+                    _ => caps[0].to_string(),
+                }
+            })
+            .into_owned();
+
+        let text = RE_YEAR
+            .replace_all(&text, |caps: &Captures| {
+                let year = caps[3]
+                    .parse()
+                    .map(year_to_words)
+                    .unwrap_or_else(|_| caps[3].to_string());
+                format!("{}{}{}", &caps[1], &caps[2], year)
+            })
+            .into_owned();
+
+        let text = RE_DECADE
+            .replace_all(&text, |caps: &Captures| {
+                let year: u64 = format!("{}{}", &caps[1], &caps[2]).parse().unwrap_or(0);
+                plural_to_words(year_to_words(year))
+            })
+            .into_owned();
+
+        let text = RE_ORDINAL
+            .replace_all(&text, |caps: &Captures| {
+                caps[1]
+                    .parse()
+                    .map(ordinal_to_words)
+                    .unwrap_or_else(|_| caps[0].to_string())
+            })
+            .into_owned();
+
+        Cow::Owned(text)
+    }
+}
+
+/// Urls, e-mail addresses, file paths and code identifiers are rewritten into words before
+/// segmentation, the separators are read in the language around them.
+pub mod token {
+    use super::*;
+    use crate::text::dict::{self, Dicts};
+    use crate::text::{Lang, acronym};
+    use regex::Captures;
+
+    struct Readings {
+        dot: &'static str,
+        at: &'static str,
+        slash: &'static str,
+        backslash: &'static str,
+        colon: &'static str,
+        dash: &'static str,
+        underscore: &'static str,
+        tilde: &'static str,
+        plus: &'static str,
+        digits: [&'static str; 10],
+    }
+
+    static EN: Readings = Readings {
+        dot: "dot",
+        at: "at",
+        slash: "slash",
+        backslash: "backslash",
+        colon: "colon",
+        dash: "dash",
+        underscore: "underscore",
+        tilde: "tilde",
+        plus: "plus",
+        digits: [
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ],
+    };
+
+    static ZH: Readings = Readings {
+        dot: "点",
+        at: "艾特",
+        slash: "斜杠",
+        backslash: "反斜杠",
+        colon: "冒号",
+        dash: "杠",
+        underscore: "下划线",
+        tilde: "波浪线",
+        plus: "加",
+        digits: ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"],
+    };
+
+    #[cfg(feature = "enable_jp")]
+    static JP: Readings = Readings {
+        dot: "ドット",
+        at: "アット",
+        slash: "スラッシュ",
+        backslash: "バックスラッシュ",
+        colon: "コロン",
+        dash: "ハイフン",
+        underscore: "アンダーバー",
+        tilde: "チルダ",
+        plus: "プラス",
+        digits: [
+            "ゼロ",
+            "いち",
+            "に",
+            "さん",
+            "よん",
+            "ご",
+            "ろく",
+            "なな",
+            "はち",
+            "きゅう",
+        ],
+    };
+
+    #[cfg(feature = "enable_ko")]
+    static KO: Readings = Readings {
+        dot: "점",
+        at: "골뱅이",
+        slash: "슬래시",
+        backslash: "역슬래시",
+        colon: "콜론",
+        dash: "하이픈",
+        underscore: "언더바",
+        tilde: "물결",
+        plus: "플러스",
+        digits: ["공", "일", "이", "삼", "사", "오", "육", "칠", "팔", "구"],
+    };
+
+    // cantonese speakers say most of the separators in english
+    #[cfg(feature = "enable_yue")]
+    static YUE: Readings = Readings {
+        dot: "點",
+        at: "at",
+        slash: "slash",
+        backslash: "backslash",
+        colon: "冒號",
+        dash: "dash",
+        underscore: "underscore",
+        tilde: "波浪號",
+        plus: "加",
+        digits: ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"],
+    };
+
+    fn readings(lang: Lang) -> &'static Readings {
+        match lang {
+            Lang::Zh => &ZH,
+            Lang::En => &EN,
+            #[cfg(feature = "enable_jp")]
+            Lang::Jp => &JP,
+            #[cfg(feature = "enable_ko")]
+            Lang::Ko => &KO,
+            #[cfg(feature = "enable_yue")]
+            Lang::Yue => &YUE,
+        }
+    }
+
+    static NAME_SUFFIXES: &str = "com|org|net|edu|gov|io|dev|ai|app|cn|jp|kr|hk|tw|uk|de|fr|ru|info|xyz|top|\
+        rs|py|js|ts|jsx|tsx|json|toml|yaml|yml|md|txt|csv|html|css|xml|pdf|docx?|xlsx?|pptx?|\
+        png|jpe?g|gif|svg|webp|mp3|mp4|wav|zip|tar|gz|exe|sh|cpp|go|java|kt|swift|rb|php|lua|sql|log|ini|cfg|conf|lock";
+
+    lazy_static! {
+        static ref RE_URL: Regex = Regex::new(
+            r"(?-u:\b)(?:([A-Za-z][A-Za-z0-9+\-]*)://|(?i:www)\.)[A-Za-z0-9\-._~/%:@+]*[A-Za-z0-9_~/\-](?:[?#][A-Za-z0-9\-._~/%:@+=&]*[A-Za-z0-9_~/=&\-])?"
+        )
+        .unwrap();
+        static ref RE_EMAIL: Regex = Regex::new(
+            r"(?-u:\b)[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,}(?-u:\b)"
+        )
+        .unwrap();
+        static ref RE_PATH: Regex = Regex::new(
+            r"(?:~|\.\.?)(?:/[A-Za-z0-9._\-]+)+/?|(?:/[A-Za-z0-9._\-]+){2,}/?|(?-u:\b)[A-Za-z]:\\(?:[A-Za-z0-9._\-]+\\?)*"
+        )
+        .unwrap();
+        static ref RE_NAME: Regex = Regex::new(&format!(
+            r"(?-u:\b)[A-Za-z0-9][A-Za-z0-9_\-]*(?:\.[A-Za-z0-9_\-]+)*\.(?i:{NAME_SUFFIXES})(?-u:\b)(?:/[A-Za-z0-9._\-]+)*"
+        ))
+        .unwrap();
+        static ref RE_SNAKE: Regex =
+            Regex::new(r"(?-u:\b)(?:_+[A-Za-z][A-Za-z0-9]*|[A-Za-z][A-Za-z0-9]*_+[A-Za-z0-9])[A-Za-z0-9]*(?:_+[A-Za-z0-9]+)*_*(?-u:\b)").unwrap();
+        static ref RE_CAMEL: Regex =
+            Regex::new(r"(?-u:\b)[A-Za-z][a-z0-9]*[A-Z][A-Za-z0-9]*(?-u:\b)").unwrap();
+    }
+
+    /// The language of the letters around `start..end`, like [`is_en_context`]
+    fn context_lang(text: &str, start: usize, end: usize) -> Lang {
+        let before = text[..start].chars().rev().find(|c| c.is_alphabetic());
+        let after = text[end..].chars().find(|c| c.is_alphabetic());
+        match before.or(after) {
+            Some(c) if c.is_ascii_alphabetic() => Lang::En,
+            #[cfg(feature = "enable_jp")]
+            Some('\u{3040}'..='\u{30ff}') => Lang::Jp,
+            #[cfg(feature = "enable_ko")]
+            Some('\u{ac00}'..='\u{d7a3}') => Lang::Ko,
+            Some(_) => Lang::Zh,
+            None if text.is_ascii() => Lang::En,
+            None => Lang::Zh,
+        }
+    }
+
+    fn is_vowel(c: char) -> bool {
+        matches!(c.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u')
+    }
+
+    /// "MAX_SIZE" is read as words while "API" and "www" are spelled, `short` also spells
+    /// suffixes like "io" and "md"
+    fn word(run: &str, short: bool, dicts: &Dicts) -> String {
+        let upper = run.to_ascii_uppercase();
+        if dicts.en_acronym(run).is_some() {
+            return run.to_string();
+        }
+        if dicts.en_acronym(&upper).is_some() {
+            return upper;
+        }
+        let b = run.as_bytes();
+        if run == upper {
+            let cvc = b.len() == 3
+                && !is_vowel(b[0] as char)
+                && is_vowel(b[1] as char)
+                && !is_vowel(b[2] as char);
+            if (b.len() >= 4 && b.iter().any(|c| is_vowel(*c as char))) || cvc {
+                return run.to_ascii_lowercase();
+            }
+            run.to_string()
+        } else if !run.chars().any(is_vowel) || (short && b.len() <= 2) {
+            upper
+        } else {
+            run.to_string()
+        }
+    }
+
+    /// letters, digits and separators of a url, an e-mail address or a path
+    fn read_token(token: &str, r: &Readings, dicts: &Dicts, out: &mut Vec<String>) {
+        let mut rest = token;
+        let mut prev = None;
+        while let Some(c) = rest.chars().next() {
+            let n = if c.is_ascii_alphanumeric() {
+                rest.find(|x: char| {
+                    x.is_ascii_digit() != c.is_ascii_digit() || !x.is_ascii_alphanumeric()
+                })
+                .unwrap_or(rest.len())
+            } else {
+                c.len_utf8()
+            };
+            let (run, tail) = rest.split_at(n);
+            let suffix = prev == Some('.');
+            rest = tail;
+            prev = Some(c);
+            if c.is_ascii_digit() {
+                out.extend(
+                    run.bytes()
+                        .map(|d| r.digits[(d - b'0') as usize].to_string()),
+                );
+                continue;
+            }
+            if c.is_ascii_alphabetic() {
+                out.extend(
+                    acronym::split_camel(run)
+                        .into_iter()
+                        .map(|p| word(p, suffix, dicts)),
+                );
+                continue;
+            }
+            let sep = match c {
+                '.' => r.dot,
+                '@' => r.at,
+                '/' => r.slash,
+                '\\' => r.backslash,
+                ':' => r.colon,
+                '-' => r.dash,
+                '_' => r.underscore,
+                '~' => r.tilde,
+                '+' => r.plus,
+                _ => continue,
+            };
+            out.push(sep.to_string());
+        }
+    }
+
+    /// words of an identifier, underscores and camel case humps are word breaks
+    fn read_ident(ident: &str, dicts: &Dicts, out: &mut Vec<String>) {
+        for piece in ident.split('_').filter(|p| !p.is_empty()) {
+            let mut rest = piece;
+            while let Some(c) = rest.chars().next() {
+                let n = rest
+                    .find(|x: char| x.is_ascii_digit() != c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let (run, tail) = rest.split_at(n);
+                rest = tail;
+                if c.is_ascii_digit() {
+                    out.push(run.to_string());
+                } else {
+                    out.extend(
+                        acronym::split_camel(run)
+                            .into_iter()
+                            .map(|p| word(p, false, dicts)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// ascii words are kept apart by spaces, the other readings are glued to them so a chinese
+    /// sentence does not get extra pauses
+    fn join(pieces: Vec<String>) -> String {
+        let mut s = String::new();
+        for p in pieces {
+            if s.ends_with(|c: char| c.is_ascii_alphanumeric())
+                && p.starts_with(|c: char| c.is_ascii_alphanumeric())
+            {
+                s.push(' ');
+            }
+            s.push_str(&p);
+        }
+        s
+    }
+
+    fn read_url(caps: &Captures, r: &Readings, dicts: &Dicts) -> String {
+        let m = caps.get(0).unwrap().as_str();
+        // the query and fragment are not read
+        let m = m.split(['?', '#']).next().unwrap_or(m);
+        let body = match caps.get(1) {
+            Some(scheme)
+                if scheme.as_str().eq_ignore_ascii_case("http")
+                    || scheme.as_str().eq_ignore_ascii_case("https") =>
+            {
+                &m[scheme.end() - caps.get(0).unwrap().start() + 3..]
+            }
+            _ => m,
+        };
+        let mut pieces = vec![];
+        read_token(body.trim_end_matches('/'), r, dicts, &mut pieces);
+        join(pieces)
+    }
+
+    /// Rewrite urls, e-mail addresses, paths, file names and `snake_case` or `camelCase`
+    /// identifiers, `lang` is the language of the request or `None` to follow the context
+    pub fn normalize(text: &str, lang: Option<Lang>) -> Cow<'_, str> {
+        if !text.contains(|c: char| c.is_ascii_alphabetic()) {
+            return Cow::Borrowed(text);
+        }
+        let lang_at = |t: &str, caps: &Captures| {
+            let m = caps.get(0).unwrap();
+            readings(lang.unwrap_or_else(|| context_lang(t, m.start(), m.end())))
+        };
+        let dicts = dict::snapshot();
+
+        let t = text;
+        let text = RE_URL
+            .replace_all(t, |caps: &Captures| {
+                read_url(caps, lang_at(t, caps), &dicts)
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_EMAIL
+            .replace_all(t, |caps: &Captures| {
+                let mut pieces = vec![];
+                read_token(&caps[0], lang_at(t, caps), &dicts, &mut pieces);
+                join(pieces)
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_PATH
+            .replace_all(t, |caps: &Captures| {
+                let m = caps.get(0).unwrap();
+                // "a/b/c" and "1/2/3" are not paths
+                if t[..m.start()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || "/.:_-".contains(c))
+                {
+                    return m.as_str().to_string();
+                }
+                let mut pieces = vec![];
+                let path = m.as_str();
+                read_token(
+                    path.strip_suffix(['/', '\\'])
+                        .filter(|p| !p.is_empty())
+                        .unwrap_or(path),
+                    lang_at(t, caps),
+                    &dicts,
+                    &mut pieces,
+                );
+                join(pieces)
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_NAME
+            .replace_all(t, |caps: &Captures| {
+                let m = caps.get(0).unwrap();
+                if t[m.end()..].starts_with(['.', '@'])
+                    && t[m.end() + 1..].starts_with(|c: char| c.is_ascii_alphanumeric())
+                {
+                    return m.as_str().to_string();
+                }
+                let mut pieces = vec![];
+                read_token(m.as_str(), lang_at(t, caps), &dicts, &mut pieces);
+                join(pieces)
+            })
+            .into_owned();
+
+        let text = RE_SNAKE
+            .replace_all(&text, |caps: &Captures| {
+                let mut pieces = vec![];
+                read_ident(&caps[0], &dicts, &mut pieces);
+                pieces.join(" ")
+            })
+            .into_owned();
+
+        let text = RE_CAMEL
+            .replace_all(&text, |caps: &Captures| {
+                let ident = &caps[0];
+                let mut pieces = vec![];
+                if dicts.en_acronym(ident).is_none() {
+                    read_ident(ident, &dicts, &mut pieces);
+                }
+                match pieces.len() {
+                    0 | 1 => ident.to_string(),
+                    _ => pieces.join(" "),
+                }
+            })
+            .into_owned();
+
+        Cow::Owned(text)
+    }
+}
+
+/// Roman numerals, circled numbers and list markers become arabic numbers before
+/// segmentation, "第IV章" is read as 第四章 and "Chapter XII" as chapter twelve.
+pub mod numeral {
+    use super::*;
+    use regex::Captures;
+
+    /// a roman numeral after these words is a number, "I" alone is only trusted here
+    static ROMAN_KEYWORDS: &str = "Chapter|Part|Volume|Vol\\.?|Book|Act|Scene|Section|Appendix|Article|Phase|Stage|Episode|Season|Level|Type|Class|Grade|Round|Step|Unit|Lesson|War|Psalm|Canto";
+
+    /// a roman numeral after these names is a regnal ordinal, "Henry VIII"
+    static REGNAL_NAMES: &str = "Alexander|Alfonso|Amenhotep|Anne|Benedict|Boniface|Carlos|Catherine|Charles|Clement|Constantine|David|Edward|Elizabeth|Felipe|Ferdinand|Francis|Frederick|George|Gregory|Gustav|Henri|Henry|Innocent|Ivan|James|John|Juan|Karl|Leo|Louis|Ludwig|Mary|Napoleon|Nicholas|Otto|Paul|Peter|Philip|Pius|Ptolemy|Ramesses|Richard|Robert|Rama|Sixtus|Thutmose|Urban|Victor|Wilhelm|William";
+    /// a title makes any name regnal, "King Foo III"
+    static REGNAL_TITLES: &str = "King|Queen|Pope|Emperor|Empress|Tsar|Czar|Kaiser|Sultan|Pharaoh|Prince|Princess|Duke|Duchess";
+
+    lazy_static! {
+        static ref RE_ROMAN: Regex =
+            Regex::new(r"^M{0,3}(?:CM|CD|D?C{0,3})(?:XC|XL|L?X{0,3})(?:IX|IV|V?I{0,3})$").unwrap();
+        static ref RE_ZH_ORDINAL: Regex = Regex::new(r"第\s*([IVXLCDM]+)(?-u:\b)").unwrap();
+        static ref RE_ZH_SUFFIX: Regex =
+            Regex::new(r"(?-u:\b)([IVXLCDM]{2,})\s*([型期级类代号式阶])").unwrap();
+        static ref RE_KEYWORD: Regex = Regex::new(&format!(
+            r"(?-u:\b)((?i:{ROMAN_KEYWORDS})\s+)([IVXLCDM]+)(?-u:\b)"
+        ))
+        .unwrap();
+        static ref RE_REGNAL: Regex = Regex::new(&format!(
+            r"(?-u:\b)((?:(?:{REGNAL_TITLES})\s+[A-Z][a-z]+|{REGNAL_NAMES})\s+)([IVX]{{1,6}})(?-u:\b)"
+        ))
+        .unwrap();
+        static ref RE_HEADING: Regex = Regex::new(r"(?m)^(\s*)([IVXL]{2,}|[IVX])([.)]\s+)").unwrap();
+        static ref RE_PAREN: Regex = Regex::new(
+            r"[(（]\s*([0-9]{1,2}|[A-Za-z]|[ivx]{2,5}|[IVX]{2,5}|[一二三四五六七八九十]{1,3})\s*[)）]"
+        )
+        .unwrap();
+        static ref RE_BRACKET: Regex = Regex::new(r"(^|[\s:：])([0-9]{1,2})[)）]").unwrap();
+    }
+
+    pub fn roman_value(s: &str) -> Option<u64> {
+        let s = s.to_ascii_uppercase();
+        if s.is_empty() || !RE_ROMAN.is_match(&s) {
+            return None;
+        }
+        let value = |c| match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            'D' => 500,
+            _ => 1000,
+        };
+        let digits = s.chars().map(value).collect::<Vec<u64>>();
+        let mut n = 0;
+        for (i, d) in digits.iter().enumerate() {
+            match digits.get(i + 1) {
+                Some(next) if next > d => n -= *d as i64,
+                _ => n += *d as i64,
+            }
+        }
+        Some(n as u64)
+    }
+
+    /// Ⅳ and ⅳ as ascii letters
+    fn roman_char(c: char) -> Option<&'static str> {
+        static ROMAN: [&str; 16] = [
+            "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII", "L", "C",
+            "D", "M",
+        ];
+        match c {
+            'Ⅰ'..='Ⅿ' => Some(ROMAN[c as usize - 'Ⅰ' as usize]),
+            'ⅰ'..='ⅿ' => Some(ROMAN[c as usize - 'ⅰ' as usize]),
+            _ => None,
+        }
+    }
+
+    enum Marker {
+        Number(u32),
+        Letter(char),
+    }
+
+    /// ①, ⑴, ⒈, ❶ and friends, ⒜ and Ⓐ are letters
+    fn circled(c: char) -> Option<Marker> {
+        let n = |base: char, first: u32| Some(Marker::Number(c as u32 - base as u32 + first));
+        let letter =
+            |base: char| char::from_u32('A' as u32 + c as u32 - base as u32).map(Marker::Letter);
+        match c {
+            '①'..='⑳' => n('①', 1),
+            '⑴'..='⒇' => n('⑴', 1),
+            '⒈'..='⒛' => n('⒈', 1),
+            '㉑'..='㉟' => n('㉑', 21),
+            '㊱'..='㊿' => n('㊱', 36),
+            '⓫'..='⓴' => n('⓫', 11),
+            '⓵'..='⓾' => n('⓵', 1),
+            '❶'..='❿' => n('❶', 1),
+            '➀'..='➉' => n('➀', 1),
+            '➊'..='➓' => n('➊', 1),
+            '⓪' | '⓿' => Some(Marker::Number(0)),
+            '⒜'..='⒵' => letter('⒜'),
+            'Ⓐ'..='Ⓩ' => letter('Ⓐ'),
+            'ⓐ'..='ⓩ' => letter('ⓐ'),
+            _ => None,
+        }
+    }
+
+    /// a marker at the start of an item gets a short pause, "图①" is just a number
+    fn starts_item(text: &str, at: usize) -> bool {
+        !text[..at].ends_with(char::is_alphanumeric)
+    }
+
+    fn fold_chars(text: &str) -> Cow<'_, str> {
+        if !text
+            .chars()
+            .any(|c| roman_char(c).is_some() || circled(c).is_some())
+        {
+            return Cow::Borrowed(text);
+        }
+        let mut r = String::with_capacity(text.len());
+        let mut roman = String::new();
+        for (i, c) in text.char_indices() {
+            if let Some(letters) = roman_char(c) {
+                roman.push_str(letters);
+                continue;
+            }
+            if !roman.is_empty() {
+                match roman_value(&roman) {
+                    Some(n) => r.push_str(&n.to_string()),
+                    None => r.push_str(&roman),
+                }
+                roman.clear();
+            }
+            match circled(c) {
+                Some(marker) => {
+                    match marker {
+                        Marker::Number(n) => r.push_str(&n.to_string()),
+                        Marker::Letter(l) => r.push(l),
+                    }
+                    if starts_item(text, i) {
+                        r.push(',');
+                    }
+                }
+                None => r.push(c),
+            }
+        }
+        if let Some(n) = roman_value(&roman) {
+            r.push_str(&n.to_string());
+        } else {
+            r.push_str(&roman);
+        }
+        Cow::Owned(r)
+    }
+
+    /// a single letter is only a heading among other headings, "I. Intro\nII. Methods",
+    /// and not before an initial or a lowercase word, "V. S. Naipaul" and "I. e."
+    fn heading(text: &str, caps: &Captures, headings: usize) -> Option<u64> {
+        let numeral = &caps[2];
+        if numeral.len() == 1 {
+            if headings < 2 {
+                return None;
+            }
+            let next = text[caps.get(0).unwrap().end()..]
+                .split_whitespace()
+                .next()
+                .unwrap_or_default();
+            let initial = next.len() == 2 && next.ends_with('.');
+            if !next.starts_with(|c: char| c.is_uppercase()) || initial {
+                return None;
+            }
+        }
+        roman_value(numeral)
+    }
+
+    /// a "(" opened earlier on the line, "(see 1)" is not a marker
+    fn in_paren(text: &str, at: usize) -> bool {
+        let line = &text[text[..at].rfind('\n').map_or(0, |i| i + 1)..at];
+        let depth = line.chars().fold(0, |depth: u32, c| match c {
+            '(' | '（' => depth + 1,
+            ')' | '）' => depth.saturating_sub(1),
+            _ => depth,
+        });
+        depth > 0
+    }
+
+    fn paren_marker(text: &str, caps: &Captures) -> String {
+        let m = caps.get(0).unwrap();
+        // f(x) and file(s) are not markers
+        if !starts_item(text, m.start()) {
+            return m.as_str().to_string();
+        }
+        let inner = &caps[1];
+        // "(i)" is a roman one, other single letters are spelled
+        let marker = if inner.starts_with(|c: char| c.is_ascii_digit()) || !inner.is_ascii() {
+            inner.to_string()
+        } else if inner == "i" || inner.len() > 1 {
+            roman_value(inner).map_or_else(|| inner.to_string(), |n| n.to_string())
+        } else {
+            inner.to_ascii_uppercase()
+        };
+        format!("{marker},")
+    }
+
+    /// Rewrite roman numerals in context, regnal numbers like "Henry VIII" into english
+    /// ordinals, and circled, parenthesized and "1)" list markers into numbers
+    pub fn normalize(text: &str) -> Cow<'_, str> {
+        let text = fold_chars(text);
+        if !text.contains(['I', 'V', 'X', 'L', 'C', 'D', 'M', ')', '）']) {
+            return text;
+        }
+        let roman = |s: &str| roman_value(s).map(|n| n.to_string());
+
+        let text = RE_ZH_ORDINAL
+            .replace_all(&text, |caps: &Captures| match roman(&caps[1]) {
+                Some(n) => format!("第{n}"),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+
+        let text = RE_ZH_SUFFIX
+            .replace_all(&text, |caps: &Captures| match roman(&caps[1]) {
+                Some(n) => format!("{n}{}", &caps[2]),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+
+        let text = RE_KEYWORD
+            .replace_all(&text, |caps: &Captures| match roman(&caps[2]) {
+                Some(n) => format!("{}{n}", &caps[1]),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+
+        // "I" and "X" alone are too often a word or a name, like "Then I" or "Malcolm X"
+        let text = RE_REGNAL
+            .replace_all(&text, |caps: &Captures| match roman_value(&caps[2]) {
+                Some(n) if n >= 2 && &caps[2] != "X" => {
+                    format!("{}the {}", &caps[1], en::ordinal_to_words(n))
+                }
+                _ => caps[0].to_string(),
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let headings = RE_HEADING.find_iter(t).count();
+        let text = RE_HEADING
+            .replace_all(t, |caps: &Captures| match heading(t, caps, headings) {
+                Some(n) => format!("{}{n}{}", &caps[1], &caps[3]),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_PAREN
+            .replace_all(t, |caps: &Captures| paren_marker(t, caps))
+            .into_owned();
+
+        let t = text.as_str();
+        let text = RE_BRACKET
+            .replace_all(t, |caps: &Captures| {
+                if in_paren(t, caps.get(0).unwrap().start()) {
+                    caps[0].to_string()
+                } else {
+                    format!("{}{},", &caps[1], &caps[2])
+                }
+            })
+            .into_owned();
+
+        Cow::Owned(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_drop_only_emoji() {
+        let config = NormalizeConfig::default();
+        assert_eq!(unicode::normalize("好👍的", &config), "好的");
+        assert_eq!(unicode::normalize("1→2", &config), "1→2");
+        assert_eq!(unicode::normalize("❶➀➊", &config), "❶➀➊");
+        let text = unicode::normalize("❶准备 ➋开始", &config);
+        assert_eq!(numeral::normalize(&text), "1,准备 2,开始");
+    }
+
+    #[test]
+    fn roman_value() {
+        assert_eq!(numeral::roman_value("IV"), Some(4));
+        assert_eq!(numeral::roman_value("xiv"), Some(14));
+        assert_eq!(numeral::roman_value("MCMXCIV"), Some(1994));
+        assert_eq!(numeral::roman_value("IIII"), None);
+        assert_eq!(numeral::roman_value("VX"), None);
+        assert_eq!(numeral::roman_value(""), None);
+    }
+
+    #[test]
+    fn year_to_words() {
+        assert_eq!(en::year_to_words(1994), "nineteen ninety four");
+        assert_eq!(en::year_to_words(1905), "nineteen oh five");
+        assert_eq!(en::year_to_words(1900), "nineteen hundred");
+        assert_eq!(en::year_to_words(2000), "two thousand");
+        assert_eq!(en::year_to_words(2008), "two thousand eight");
+        assert_eq!(en::year_to_words(2024), "twenty twenty four");
+    }
+
+    #[test]
+    fn circled() {
+        assert_eq!(numeral::normalize("①准备 ②开始"), "1,准备 2,开始");
+        assert_eq!(numeral::normalize("见图①所示"), "见图1所示");
+        assert_eq!(
+            numeral::normalize("⑴ ⒛ ㉑ ❿ ➉ ➓ ⓪"),
+            "1, 20, 21, 10, 10, 10, 0,"
+        );
+        assert_eq!(numeral::normalize("Ⓐ选项 ⒝选项"), "A,选项 B,选项");
+        assert_eq!(numeral::normalize("Ⅱ型 ⅻ"), "2型 12");
+    }
+
+    #[test]
+    fn roman_in_context() {
+        assert_eq!(numeral::normalize("第IV章"), "第4章");
+        assert_eq!(numeral::normalize("II期"), "2期");
+        assert_eq!(numeral::normalize("Chapter XII"), "Chapter 12");
+        assert_eq!(numeral::normalize("World War II"), "World War 2");
+        assert_eq!(numeral::normalize("Type II error"), "Type 2 error");
+    }
+
+    #[test]
+    fn regnal() {
+        assert_eq!(
+            numeral::normalize("Henry VIII met Elizabeth II"),
+            "Henry the eighth met Elizabeth the second"
+        );
+        assert_eq!(numeral::normalize("King Harald V"), "King Harald the fifth");
+        assert_eq!(numeral::normalize("Final Fantasy VII"), "Final Fantasy VII");
+        assert_eq!(numeral::normalize("Malcolm X"), "Malcolm X");
+        assert_eq!(numeral::normalize("Then I went"), "Then I went");
+    }
+
+    #[test]
+    fn heading() {
+        assert_eq!(
+            numeral::normalize("I. Intro\nII. Methods\nIII. Results"),
+            "1. Intro\n2. Methods\n3. Results"
+        );
+        assert_eq!(numeral::normalize("IV. Results"), "4. Results");
+        assert_eq!(numeral::normalize("C. S. Lewis"), "C. S. Lewis");
+        assert_eq!(numeral::normalize("I. e. this"), "I. e. this");
+        assert_eq!(numeral::normalize("I. Intro"), "I. Intro");
+        assert_eq!(
+            numeral::normalize("I. Intro\nV. S. Naipaul\nII. End"),
+            "1. Intro\nV. S. Naipaul\n2. End"
+        );
+    }
+
+    #[test]
+    fn list_markers() {
+        assert_eq!(numeral::normalize("1) open\n2) close"), "1, open\n2, close");
+        assert_eq!(
+            numeral::normalize("steps: 1) open 2) close"),
+            "steps: 1, open 2, close"
+        );
+        assert_eq!(numeral::normalize("(see 1)"), "(see 1)");
+        assert_eq!(
+            numeral::normalize("(see 1) then 2) go"),
+            "(see 1) then 2, go"
+        );
+        assert_eq!(
+            numeral::normalize("(a) apples f(x) file(s)"),
+            "A, apples f(x) file(s)"
+        );
+        assert_eq!(numeral::normalize("(ii) and (i)"), "2, and 1,");
+        assert_eq!(numeral::normalize("（一）总则"), "一,总则");
+    }
+}
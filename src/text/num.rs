@@ -1,642 +1,1860 @@
 use crate::error::*;
-use std::collections::LinkedList;
+use crate::text::PhoneBuilder;
+use pest::iterators::Pair;
+use std::borrow::Cow;
+use tracing::warn;
 
 #[derive(pest_derive::Parser)]
 #[grammar = "resource/rule.pest"]
 pub struct ExprParser;
 
-pub mod zh {
-    use crate::text::PhoneBuilder;
+/// How the words around a number ask for it to be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumHint {
+    #[default]
+    Auto,
+    Score,
+    Ratio,
+    /// a japanese, korean or cantonese counter right after the number, it is read together with
+    /// the number
+    Counter(&'static str),
+}
 
-    use super::*;
-    use pest::iterators::Pair;
-    use tracing::{debug, warn};
-
-    fn parse_pn(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::pn);
-        match pair.as_str() {
-            "+" => builder.push_zh_word("加"),
-            "-" => builder.push_zh_word("减"),
-            "*" | "×" => builder.push_zh_word("乘"),
-            "/" | "÷" => builder.push_zh_word("除以"),
-            "=" => builder.push_zh_word("等于"),
+static ZH_SCORE_WORDS: [&str; 13] = [
+    "比分", "比赛", "战胜", "击败", "打败", "获胜", "领先", "落后", "战平", "赢", "输", "胜", "负",
+];
+static ZH_RATIO_WORDS: [&str; 5] = ["比例", "比率", "配比", "比值", "比重"];
+static EN_SCORE_WORDS: [&str; 17] = [
+    "score", "scored", "won", "win", "wins", "lost", "lose", "loses", "beat", "beats", "defeated",
+    "lead", "leads", "led", "draw", "drew", "victory",
+];
+static EN_RATIO_WORDS: [&str; 4] = ["ratio", "proportion", "scale", "odds"];
+
+impl NumHint {
+    /// `prev` and `next` are the texts right before and after the number
+    pub fn from_context(prev: &str, next: &str) -> Self {
+        let prev_zh = prev.chars().rev().take(8).collect::<Vec<_>>();
+        let prev_zh = prev_zh.into_iter().rev().collect::<String>();
+        let next_zh = next.chars().take(8).collect::<String>();
+        let en_words = prev
+            .rsplit(|c: char| !c.is_ascii_alphabetic())
+            .filter(|w| !w.is_empty())
+            .take(3)
+            .chain(
+                next.split(|c: char| !c.is_ascii_alphabetic())
+                    .filter(|w| !w.is_empty())
+                    .take(3),
+            )
+            .map(|w| w.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+
+        let has = |zh: &[&str], en: &[&str]| {
+            zh.iter()
+                .any(|w| prev_zh.contains(w) || next_zh.contains(w))
+                || en_words.iter().any(|w| en.contains(&w.as_str()))
+        };
+        if has(&ZH_RATIO_WORDS, &EN_RATIO_WORDS) {
+            NumHint::Ratio
+        } else if has(&ZH_SCORE_WORDS, &EN_SCORE_WORDS) {
+            NumHint::Score
+        } else {
+            NumHint::Auto
+        }
+    }
+}
+
+enum RangeKind {
+    Range,
+    Years,
+    Score,
+    Minus,
+}
+
+fn is_year(pair: &Pair<Rule>) -> bool {
+    let s = pair.as_str();
+    s.len() == 4 && s.parse::<u64>().is_ok_and(|y| (1000..2100).contains(&y))
+}
+
+fn range_kind(left: &Pair<Rule>, link: &str, right: &Pair<Rule>, hint: NumHint) -> RangeKind {
+    let l = left.as_str().parse::<f64>().ok();
+    let r = right.as_str().parse::<f64>().ok();
+    if hint != NumHint::Auto && l.is_some() && r.is_some() {
+        return RangeKind::Score;
+    }
+    if is_year(left) && is_year(right) && l < r {
+        return RangeKind::Years;
+    }
+    match (link, l, r) {
+        ("-", Some(l), Some(r)) if l >= r => RangeKind::Minus,
+        _ => RangeKind::Range,
+    }
+}
+
+/// hh:mm or hh:mm:ss, the hint can still ask for a score like "10:30"
+fn is_clock(parts: &[Pair<Rule>], hint: NumHint) -> bool {
+    hint == NumHint::Auto
+        && (2..=3).contains(&parts.len())
+        && parts[0].as_str().parse::<u64>().is_ok_and(|h| h <= 24)
+        && parts[1..]
+            .iter()
+            .all(|p| p.as_str().len() == 2 && p.as_str().parse::<u64>().is_ok_and(|m| m < 60))
+}
+
+/// the exponent of `power` as written, "2" for x^2
+fn power_exponent<'i>(pair: &Pair<'i, Rule>) -> &'i str {
+    pair.as_str().trim_start_matches(['^', ' '])
+}
+
+/// "x²" -> "x^2" and "10⁻³" -> "10^-3", the grammar only knows "^"
+pub fn fold_superscripts(text: &str) -> Cow<'_, str> {
+    let sup = |c: char| match c {
+        '⁰' | '⁴'..='⁹' => char::from_digit(c as u32 - 0x2070, 10),
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁻' => Some('-'),
+        _ => None,
+    };
+    if !text.chars().any(|c| sup(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let mut r = String::with_capacity(text.len() + 2);
+    let mut in_sup = false;
+    for c in text.chars() {
+        match sup(c) {
+            Some(d) => {
+                if !in_sup {
+                    r.push('^');
+                    in_sup = true;
+                }
+                r.push(d);
+            }
+            None => {
+                in_sup = false;
+                r.push(c);
+            }
+        }
+    }
+    Cow::Owned(r)
+}
+
+/// A word read before or after the number it belongs to.
+pub enum Affix {
+    Before(&'static str),
+    After(&'static str),
+}
+
+/// The words a language reads numbers with. The walk over the parse tree is shared, a language
+/// only gives the words of each part and reads the parts that do not fit the tables.
+pub trait Reader {
+    /// 0 to 9, read one by one
+    const DIGITS: [&'static str; 10];
+    /// the integer part of ".5"
+    const ZERO: &'static str = Self::DIGITS[0];
+    const POINT: &'static str;
+    const PERCENT: Affix;
+    /// between the denominator and the numerator of a fraction
+    const OVER: &'static str;
+    /// before and after the exponent of 1.2e-5, the second may be empty
+    const SCI: (&'static str, &'static str);
+    /// x^2 and x^3, when they are not read like other powers
+    const SQUARED: Option<(&'static str, &'static str)>;
+    /// before and after any other exponent, the second may be empty
+    const POWER: (&'static str, &'static str);
+    /// the square and the cube root
+    const ROOTS: (&'static str, &'static str);
+    /// the links of a range, a score or ratio and a difference
+    const TO: &'static str;
+    const VERSUS: &'static str;
+    const MINUS: &'static str;
+
+    fn push(builder: &mut PhoneBuilder, words: &str);
+
+    /// a whole number, 120 -> 一百二十
+    fn cardinal(builder: &mut PhoneBuilder, digits: &str);
+
+    /// +, -, = and the other operators
+    fn operator(op: &str) -> &'static str;
+
+    /// the sign before a number
+    fn flag(flag: &str) -> &'static str;
+
+    fn greek_name(c: &str) -> &'static str;
+
+    /// hh:mm or hh:mm:ss
+    fn clock(builder: &mut PhoneBuilder, parts: &[u64]);
+
+    /// one digit after another, 120 -> 一二零
+    fn digits(builder: &mut PhoneBuilder, digits: &str) {
+        for d in digits.chars().filter_map(|c| c.to_digit(10)) {
+            Self::push(builder, Self::DIGITS[d as usize]);
+        }
+    }
+
+    /// 1.5, .5 and 5.
+    fn decimals(builder: &mut PhoneBuilder, text: &str) {
+        read_decimals::<Self>(builder, text);
+    }
+
+    /// the denominator is read first, 3/4 -> 四分之三
+    fn fraction(builder: &mut PhoneBuilder, numerator: &str, denominator: &str) {
+        Self::cardinal(builder, denominator);
+        Self::push(builder, Self::OVER);
+        Self::cardinal(builder, numerator);
+    }
+
+    /// a year in a range like 1990-2000
+    fn year(builder: &mut PhoneBuilder, digits: &str) {
+        Self::cardinal(builder, digits);
+    }
+
+    /// a latin letter, as a variable or in a name
+    fn letter(builder: &mut PhoneBuilder, s: &str) {
+        builder.push_en_word(s);
+    }
+
+    fn greek(builder: &mut PhoneBuilder, c: &str) {
+        Self::push(builder, Self::greek_name(c));
+    }
+
+    /// between the words of a name like A-1
+    fn link(builder: &mut PhoneBuilder, _link: &str) {
+        builder.push_punctuation(",");
+    }
+
+    /// between the parts of a serial number like 123-456
+    fn serial_link(builder: &mut PhoneBuilder) {
+        builder.push_punctuation(",");
+    }
+
+    /// a number read together with the counter after it, 3本 -> さんぼん
+    fn counted(builder: &mut PhoneBuilder, n: u64, counter: &str) {
+        Self::cardinal(builder, &n.to_string());
+        Self::counter(builder, counter);
+    }
+
+    /// the counter after a number it is not read with, like 1.5本
+    fn counter(builder: &mut PhoneBuilder, counter: &str) {
+        Self::push(builder, counter);
+    }
+}
+
+fn read_decimals<R: Reader + ?Sized>(builder: &mut PhoneBuilder, text: &str) {
+    let (i_part, f_part) = text.split_once('.').unwrap_or((text, ""));
+    match i_part {
+        "" => R::push(builder, R::ZERO),
+        i_part => R::cardinal(builder, i_part),
+    }
+    if !f_part.is_empty() {
+        R::push(builder, R::POINT);
+        R::digits(builder, f_part);
+    }
+}
+
+fn parse_pn<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::pn);
+    R::push(builder, R::operator(pair.as_str()));
+    Ok(())
+}
+
+fn parse_flag<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::flag);
+    R::push(builder, R::flag(pair.as_str()));
+    Ok(())
+}
+
+fn parse_integer<R: Reader>(
+    pair: Pair<Rule>,
+    builder: &mut PhoneBuilder,
+    unit: bool,
+) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::integer);
+    if unit {
+        R::cardinal(builder, pair.as_str());
+    } else {
+        R::digits(builder, pair.as_str());
+    }
+    Ok(())
+}
+
+fn parse_decimals<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::decimals);
+    R::decimals(builder, pair.as_str());
+    Ok(())
+}
+
+fn parse_percent<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::percent);
+    // percent = { (decimals|integer)~"%" }
+
+    if let Affix::Before(word) = R::PERCENT {
+        R::push(builder, word);
+    }
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::decimals => parse_decimals::<R>(pair, builder)?,
+            Rule::integer => parse_integer::<R>(pair, builder, true)?,
             _ => {
                 #[cfg(debug_assertions)]
-                unreachable!("unknown: {:?} in pn", pair);
+                unreachable!("unknown: {:?} in percent", pair.as_str());
             }
         }
-        Ok(())
     }
+    if let Affix::After(word) = R::PERCENT {
+        R::push(builder, word);
+    }
+    Ok(())
+}
 
-    fn parse_flag(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::flag);
-        match pair.as_str() {
-            "+" => builder.push_zh_word("正"),
-            "-" => builder.push_zh_word("负"),
+fn parse_fractional<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::fractional);
+
+    let mut inner = pair.into_inner();
+    let numerator = inner.next().unwrap();
+    let denominator = inner.next().unwrap();
+    R::fraction(builder, numerator.as_str(), denominator.as_str());
+    Ok(())
+}
+
+fn parse_num<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::num);
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::flag => parse_flag::<R>(pair, builder)?,
+            Rule::percent => parse_percent::<R>(pair, builder)?,
+            Rule::sci => parse_sci::<R>(pair, builder)?,
+            Rule::decimals => parse_decimals::<R>(pair, builder)?,
+            Rule::fractional => parse_fractional::<R>(pair, builder)?,
+            Rule::integer => parse_integer::<R>(pair, builder, true)?,
             _ => {
                 #[cfg(debug_assertions)]
-                unreachable!("unknown: {:?} in flag", pair);
+                unreachable!("unknown: {:?} in num", pair.as_str());
             }
         }
-        Ok(())
     }
+    Ok(())
+}
 
-    fn parse_percent(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::percent);
-        // percent = { (decimals|integer)~"%" }
+fn parse_sci<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::sci);
 
-        builder.push_zh_word("百分之");
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::decimals => parse_decimals(pair, builder)?,
-                Rule::integer => {
-                    parse_integer(pair, builder, true)?;
-                }
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in expr", pair.as_str());
-                }
+    // 1.2e-5 -> 一点二乘十的负五次方
+    let mut inner = pair.into_inner();
+    let mantissa = inner.next().unwrap();
+    if mantissa.as_rule() == Rule::decimals {
+        parse_decimals::<R>(mantissa, builder)?;
+    } else {
+        parse_integer::<R>(mantissa, builder, true)?;
+    }
+    let (before, after) = R::SCI;
+    R::push(builder, before);
+    for pair in inner {
+        match pair.as_rule() {
+            Rule::flag => parse_flag::<R>(pair, builder)?,
+            Rule::integer => parse_integer::<R>(pair, builder, true)?,
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in sci", pair.as_str());
             }
         }
-        Ok(())
     }
+    if !after.is_empty() {
+        R::push(builder, after);
+    }
+    Ok(())
+}
 
-    static UNITS: [&str; 4] = ["", "十", "百", "千"];
-    static BASE_UNITS: [&str; 4] = ["", "万", "亿", "万"];
+fn parse_var<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::var);
+    let s = pair.as_str();
+    if s.is_ascii() {
+        R::letter(builder, s);
+    } else {
+        R::greek(builder, s);
+    }
+    Ok(())
+}
 
-    fn parse_integer(
-        pair: Pair<Rule>,
-        builder: &mut PhoneBuilder,
-        unit: bool,
-    ) -> Result<LinkedList<(String, String)>> {
-        assert_eq!(pair.as_rule(), Rule::integer);
-
-        let mut r: LinkedList<(String, String)> = LinkedList::new();
-
-        let inner = pair.into_inner().rev();
-
-        for (n, pair) in inner.enumerate() {
-            let txt = match pair.as_str() {
-                "0" => "零",
-                "1" => "一",
-                "2" => "二",
-                "3" => "三",
-                "4" => "四",
-                "5" => "五",
-                "6" => "六",
-                "7" => "七",
-                "8" => "八",
-                "9" => "九",
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in integer", n);
-                    #[cfg(not(debug_assertions))]
-                    ""
+fn parse_power<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::power);
+
+    // x^2 -> x的平方, 10^-3 -> 十的负三次方
+    match (power_exponent(&pair), R::SQUARED) {
+        ("2", Some((squared, _))) => R::push(builder, squared),
+        ("3", Some((_, cubed))) => R::push(builder, cubed),
+        _ => {
+            let (before, after) = R::POWER;
+            R::push(builder, before);
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::flag => parse_flag::<R>(pair, builder)?,
+                    Rule::decimals => parse_decimals::<R>(pair, builder)?,
+                    Rule::integer => parse_integer::<R>(pair, builder, true)?,
+                    Rule::var => parse_var::<R>(pair, builder)?,
+                    _ => {
+                        #[cfg(debug_assertions)]
+                        unreachable!("unknown: {:?} in power", pair.as_str());
+                    }
                 }
-            };
-            let u = if n % 4 != 0 {
-                UNITS[n % 4]
-            } else {
-                BASE_UNITS[(n / 4) % 4]
-            };
+            }
+            if !after.is_empty() {
+                R::push(builder, after);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_root<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::root);
+    let (square, cube) = R::ROOTS;
+    match pair.as_str() {
+        "∛" => R::push(builder, cube),
+        _ => R::push(builder, square),
+    }
+    Ok(())
+}
+
+fn parse_signs<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::signs);
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::num => parse_num::<R>(pair, builder)?,
+            Rule::pn => parse_pn::<R>(pair, builder)?,
+            Rule::var => parse_var::<R>(pair, builder)?,
+            Rule::power => parse_power::<R>(pair, builder)?,
+            Rule::root => parse_root::<R>(pair, builder)?,
+            Rule::word => {
+                warn!("word: {:?}", pair.as_str());
+            }
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in expr", pair.as_str());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_word<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::word);
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::digit => R::digits(builder, pair.as_str()),
+            Rule::alpha => R::letter(builder, pair.as_str()),
+            Rule::greek => R::greek(builder, pair.as_str()),
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in word", pair.as_str());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_ident<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::ident);
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::word => parse_word::<R>(pair, builder)?,
+            Rule::link => R::link(builder, pair.as_str()),
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in ident", pair.as_str());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_range_num<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::range_num);
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::percent => parse_percent::<R>(pair, builder)?,
+            Rule::decimals => parse_decimals::<R>(pair, builder)?,
+            Rule::integer => parse_integer::<R>(pair, builder, true)?,
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in range_num", pair.as_str());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_range<R: Reader>(
+    pair: Pair<Rule>,
+    builder: &mut PhoneBuilder,
+    hint: NumHint,
+) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::range);
+
+    let mut inner = pair.into_inner();
+    let left = inner.next().unwrap();
+    let link = inner.next().unwrap();
+    let right = inner.next().unwrap();
+    let kind = range_kind(&left, link.as_str(), &right, hint);
+    if let RangeKind::Years = kind {
+        R::year(builder, left.as_str());
+        R::push(builder, R::TO);
+        R::year(builder, right.as_str());
+        return Ok(());
+    }
+    parse_range_num::<R>(left, builder)?;
+    match kind {
+        RangeKind::Score => R::push(builder, R::VERSUS),
+        RangeKind::Minus => R::push(builder, R::MINUS),
+        _ => R::push(builder, R::TO),
+    }
+    parse_range_num::<R>(right, builder)?;
+    Ok(())
+}
+
+fn parse_ratio<R: Reader>(
+    pair: Pair<Rule>,
+    builder: &mut PhoneBuilder,
+    hint: NumHint,
+) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::ratio);
+
+    let parts = pair.into_inner().collect::<Vec<_>>();
+    if is_clock(&parts, hint) {
+        let parts = parts
+            .iter()
+            .map(|p| p.as_str().parse::<u64>().unwrap_or_default())
+            .collect::<Vec<_>>();
+        R::clock(builder, &parts);
+        return Ok(());
+    }
+
+    for (i, part) in parts.into_iter().enumerate() {
+        if i > 0 {
+            R::push(builder, R::VERSUS);
+        }
+        parse_integer::<R>(part, builder, true)?;
+    }
+    Ok(())
+}
 
-            r.push_front((txt.to_string(), u.to_string()));
+fn parse_serial<R: Reader>(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::serial);
+
+    for (i, part) in pair.into_inner().enumerate() {
+        if i > 0 {
+            R::serial_link(builder);
         }
+        parse_integer::<R>(part, builder, false)?;
+    }
+    Ok(())
+}
+
+/// Read a number in the language of `R`, like `parse_all::<zh::Zh>`
+pub fn parse_all<R: Reader>(
+    pair: Pair<Rule>,
+    builder: &mut PhoneBuilder,
+    hint: NumHint,
+) -> Result<()> {
+    assert_eq!(pair.as_rule(), Rule::all);
+
+    let (hint, counter) = match hint {
+        NumHint::Counter(c) => (NumHint::Auto, Some(c)),
+        h => (h, None),
+    };
+    if let Some(counter) = counter
+        && let Ok(n) = pair.as_str().trim().parse::<u64>()
+    {
+        R::counted(builder, n, counter);
+        return Ok(());
+    }
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::range => parse_range::<R>(pair, builder, hint)?,
+            Rule::ratio => parse_ratio::<R>(pair, builder, hint)?,
+            Rule::serial => parse_serial::<R>(pair, builder)?,
+            Rule::signs => parse_signs::<R>(pair, builder)?,
+            Rule::ident => parse_ident::<R>(pair, builder)?,
+            _ => {
+                #[cfg(debug_assertions)]
+                unreachable!("unknown: {:?} in all", pair.as_str());
+            }
+        }
+    }
+    if let Some(counter) = counter {
+        R::counter(builder, counter);
+    }
+    Ok(())
+}
+
+pub mod zh {
+    use super::*;
+
+    pub struct Zh;
+
+    static UNITS: [&str; 4] = ["", "十", "百", "千"];
+    static BASE_UNITS: [&str; 4] = ["", "万", "亿", "万"];
 
-        if r.iter().all(|(s, _)| s == "零") {
-            builder.push_zh_word("零");
-            return Ok(r);
+    impl Reader for Zh {
+        const DIGITS: [&'static str; 10] =
+            ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+        const POINT: &'static str = "点";
+        const PERCENT: Affix = Affix::Before("百分之");
+        const OVER: &'static str = "分之";
+        const SCI: (&'static str, &'static str) = ("乘十的", "次方");
+        const SQUARED: Option<(&'static str, &'static str)> = Some(("的平方", "的立方"));
+        const POWER: (&'static str, &'static str) = ("的", "次方");
+        const ROOTS: (&'static str, &'static str) = ("根号", "三次根号");
+        const TO: &'static str = "到";
+        const VERSUS: &'static str = "比";
+        const MINUS: &'static str = "减";
+
+        fn push(builder: &mut PhoneBuilder, words: &str) {
+            builder.push_zh_word(words);
         }
 
-        if unit {
+        fn cardinal(builder: &mut PhoneBuilder, digits: &str) {
+            let mut r = digits
+                .chars()
+                .rev()
+                .enumerate()
+                .map(|(n, c)| {
+                    let u = if n % 4 != 0 {
+                        UNITS[n % 4]
+                    } else {
+                        BASE_UNITS[(n / 4) % 4]
+                    };
+                    (Self::DIGITS[c.to_digit(10).unwrap_or_default() as usize], u)
+                })
+                .collect::<Vec<_>>();
+            r.reverse();
+
+            if r.iter().all(|(s, _)| *s == "零") {
+                builder.push_zh_word("零");
+                return;
+            }
+
             let mut last_is_zero = true;
-            for (s, u) in &r {
+            for (s, u) in r {
                 if s == "零" {
-                    if !BASE_UNITS.contains(&u.as_str()) {
+                    if !BASE_UNITS.contains(&u) {
                         if !last_is_zero {
                             builder.push_zh_word(s);
                             last_is_zero = true;
                         }
-                    } else {
+                    } else if !u.is_empty() {
                         builder.push_zh_word(u);
                     }
                 } else {
                     builder.push_zh_word(s);
-                    builder.push_zh_word(u);
+                    if !u.is_empty() {
+                        builder.push_zh_word(u);
+                    }
                     last_is_zero = false;
                 }
             }
-        } else {
-            for (s, _) in &r {
-                builder.push_zh_word(s);
+        }
+
+        fn operator(op: &str) -> &'static str {
+            match op {
+                "+" => "加",
+                "-" => "减",
+                "*" | "×" => "乘",
+                "/" | "÷" => "除以",
+                "=" => "等于",
+                ">=" | "≥" => "大于等于",
+                "<=" | "≤" => "小于等于",
+                "!=" | "≠" => "不等于",
+                ">" => "大于",
+                "<" => "小于",
+                "≈" => "约等于",
+                "±" => "正负",
+                _ => {
+                    #[cfg(debug_assertions)]
+                    unreachable!("unknown: {:?} in pn", op);
+                    #[cfg(not(debug_assertions))]
+                    ""
+                }
             }
         }
 
-        Ok(r)
-    }
+        fn flag(flag: &str) -> &'static str {
+            match flag {
+                "±" => "正负",
+                _ => "负",
+            }
+        }
+
+        fn greek_name(c: &str) -> &'static str {
+            match c {
+                "α" | "Α" => "阿尔法",
+                "β" | "Β" => "贝塔",
+                "γ" | "Γ" => "伽马",
+                "δ" | "Δ" => "德尔塔",
+                "ε" | "Ε" => "艾普西隆",
+                "ζ" | "Ζ" => "泽塔",
+                "η" | "Η" => "伊塔",
+                "θ" | "Θ" => "西塔",
+                "ι" | "Ι" => "约塔",
+                "κ" | "Κ" => "卡帕",
+                "λ" | "Λ" => "兰姆达",
+                "μ" | "Μ" => "缪",
+                "ν" | "Ν" => "纽",
+                "ξ" | "Ξ" => "克西",
+                "ο" | "Ο" => "奥密克戎",
+                "π" | "Π" => "派",
+                "ρ" | "Ρ" => "柔",
+                "σ" | "Σ" | "ς" => "西格玛",
+                "τ" | "Τ" => "陶",
+                "υ" | "Υ" => "宇普西隆",
+                "φ" | "Φ" => "斐",
+                "χ" | "Χ" => "卡伊",
+                "ψ" | "Ψ" => "普赛",
+                "ω" | "Ω" => "欧米伽",
+                _ => {
+                    #[cfg(debug_assertions)]
+                    unreachable!("unknown: {:?} in greek", c);
+                    #[cfg(not(debug_assertions))]
+                    ""
+                }
+            }
+        }
+
+        /// 10:05:30 -> 十点零五分三十秒, 10:00 -> 十点
+        fn clock(builder: &mut PhoneBuilder, parts: &[u64]) {
+            let last = parts.len() - 1;
+            for (i, &n) in parts.iter().enumerate() {
+                if i > 0 && i == last && n == 0 {
+                    break;
+                }
+                match i {
+                    0 if n == 2 => builder.push_zh_word("两"),
+                    0 => Self::cardinal(builder, &n.to_string()),
+                    _ => {
+                        if n < 10 && n > 0 {
+                            builder.push_zh_word("零");
+                        }
+                        Self::cardinal(builder, &n.to_string());
+                    }
+                }
+                builder.push_zh_word(["点", "分", "秒"][i]);
+            }
+        }
 
-    fn parse_decimals(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::decimals);
+        /// 2020 -> 二零二零
+        fn year(builder: &mut PhoneBuilder, digits: &str) {
+            Self::digits(builder, digits);
+        }
 
-        let mut inner = pair.into_inner().rev();
-        let f_part = inner.next().unwrap();
-        if let Some(i_part) = inner.next() {
-            parse_integer(i_part, builder, true)?;
-        } else {
-            builder.push_zh_word("零");
+        fn link(builder: &mut PhoneBuilder, link: &str) {
+            match link {
+                "-" => builder.push_zh_word("杠"),
+                _ => builder.push_punctuation("…"),
+            }
         }
-        builder.push_zh_word("点");
-        parse_integer(f_part, builder, false)?;
 
-        Ok(())
+        fn serial_link(builder: &mut PhoneBuilder) {
+            builder.push_zh_word("杠");
+        }
     }
+}
 
-    fn parse_fractional(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::fractional);
+pub mod en {
+    use crate::text::normalize;
 
-        let mut inner = pair.into_inner();
-        let numerator = inner.next().unwrap();
-        let denominator = inner.next().unwrap();
-        parse_integer(denominator, builder, true)?;
-        builder.push_zh_word("分之");
-        parse_integer(numerator, builder, true)?;
+    use super::super::SEPARATOR;
+    use super::*;
 
-        Ok(())
-    }
+    pub struct En;
+
+    impl Reader for En {
+        const DIGITS: [&'static str; 10] = [
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+        const POINT: &'static str = "point";
+        const PERCENT: Affix = Affix::After("percent");
+        const OVER: &'static str = "over";
+        const SCI: (&'static str, &'static str) = ("times ten to the power of", "");
+        const SQUARED: Option<(&'static str, &'static str)> = Some(("squared", "cubed"));
+        const POWER: (&'static str, &'static str) = ("to the power of", "");
+        const ROOTS: (&'static str, &'static str) = ("the square root of", "the cube root of");
+        const TO: &'static str = "to";
+        const VERSUS: &'static str = "to";
+        const MINUS: &'static str = "minus";
+
+        fn push(builder: &mut PhoneBuilder, words: &str) {
+            words.split([' ', '-']).for_each(|s| {
+                builder.push_en_word(s);
+                builder.push_punctuation(SEPARATOR);
+            });
+        }
 
-    fn parse_num(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::num);
+        fn cardinal(builder: &mut PhoneBuilder, digits: &str) {
+            match num2en::str_to_words(digits) {
+                Ok(r) => Self::push(builder, &r),
+                Err(_) => Self::digits(builder, digits),
+            }
+        }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::flag => parse_flag(pair, builder)?,
-                Rule::percent => parse_percent(pair, builder)?,
-                Rule::decimals => {
-                    parse_decimals(pair, builder)?;
-                }
-                Rule::fractional => {
-                    parse_fractional(pair, builder)?;
-                }
-                Rule::integer => {
-                    parse_integer(pair, builder, true)?;
-                }
+        fn operator(op: &str) -> &'static str {
+            match op {
+                "+" => "plus",
+                "-" => "minus",
+                "*" | "×" => "times",
+                "/" | "÷" => "divided by",
+                "=" => "is",
+                ">=" | "≥" => "is greater than or equal to",
+                "<=" | "≤" => "is less than or equal to",
+                "!=" | "≠" => "is not equal to",
+                ">" => "is greater than",
+                "<" => "is less than",
+                "≈" => "is approximately",
+                "±" => "plus or minus",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in num", pair.as_str());
+                    unreachable!("unknown: {:?} in pn", op);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        Ok(())
-    }
 
-    fn parse_signs(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::signs);
+        fn flag(flag: &str) -> &'static str {
+            match flag {
+                "±" => "plus or minus",
+                _ => "negative",
+            }
+        }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            debug!("{:?}", pair);
-            match pair.as_rule() {
-                Rule::num => parse_num(pair, builder)?,
-                Rule::pn => parse_pn(pair, builder)?,
-                Rule::word => {
-                    warn!("word: {:?}", pair.as_str());
-                }
+        fn greek_name(c: &str) -> &'static str {
+            match c {
+                "α" | "Α" => "alpha",
+                "β" | "Β" => "beta",
+                "γ" | "Γ" => "gamma",
+                "δ" | "Δ" => "delta",
+                "ε" | "Ε" => "epsilon",
+                "ζ" | "Ζ" => "zeta",
+                "η" | "Η" => "eta",
+                "θ" | "Θ" => "theta",
+                "ι" | "Ι" => "iota",
+                "κ" | "Κ" => "kappa",
+                "λ" | "Λ" => "lambda",
+                "μ" | "Μ" => "mu",
+                "ν" | "Ν" => "nu",
+                "ξ" | "Ξ" => "xi",
+                "ο" | "Ο" => "omicron",
+                "π" | "Π" => "pi",
+                "ρ" | "Ρ" => "rho",
+                "σ" | "Σ" | "ς" => "sigma",
+                "τ" | "Τ" => "tau",
+                "υ" | "Υ" => "upsilon",
+                "φ" | "Φ" => "phi",
+                "χ" | "Χ" => "chi",
+                "ψ" | "Ψ" => "psi",
+                "ω" | "Ω" => "omega",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in expr", pair.as_str());
+                    unreachable!("unknown: {:?} in greek", c);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        Ok(())
-    }
-
-    fn parse_link(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::link);
 
-        match pair.as_str() {
-            "-" => builder.push_zh_word("杠"),
-            _ => builder.push_punctuation("…"),
+        fn clock(builder: &mut PhoneBuilder, parts: &[u64]) {
+            Self::push(builder, &normalize::en::clock_to_words(parts[0], parts[1]));
+            if let Some(second) = parts.get(2) {
+                Self::push(builder, "and");
+                Self::push(builder, &normalize::en::cardinal_to_words(*second));
+                Self::push(builder, "seconds");
+            }
         }
 
-        Ok(())
-    }
+        fn decimals(builder: &mut PhoneBuilder, text: &str) {
+            match num2en::str_to_words(text) {
+                Ok(r) => Self::push(builder, &r),
+                Err(_) => read_decimals::<Self>(builder, text),
+            }
+        }
 
-    fn parse_word(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::word);
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::digit => {
-                    let txt = match pair.as_str() {
-                        "0" => "零",
-                        "1" => "一",
-                        "2" => "二",
-                        "3" => "三",
-                        "4" => "四",
-                        "5" => "五",
-                        "6" => "六",
-                        "7" => "七",
-                        "8" => "八",
-                        "9" => "九",
-                        n => {
-                            #[cfg(debug_assertions)]
-                            unreachable!("unknown: {:?} in integer", n);
+        fn fraction(builder: &mut PhoneBuilder, numerator: &str, denominator: &str) {
+            Self::cardinal(builder, numerator);
+            Self::push(builder, Self::OVER);
+            Self::cardinal(builder, denominator);
+        }
 
-                            #[cfg(not(debug_assertions))]
-                            n
-                        }
-                    };
-                    builder.push_zh_word(txt);
-                }
-                Rule::alpha => {
-                    let txt = pair.as_str();
-                    builder.push_en_word(txt);
-                }
-                Rule::greek => {
-                    let txt = match pair.as_str() {
-                        "α" | "Α" => "阿尔法",
-                        "β" | "Β" => "贝塔",
-                        "γ" | "Γ" => "伽马",
-                        "δ" | "Δ" => "德尔塔",
-                        "ε" | "Ε" => "艾普西龙",
-                        "ζ" | "Ζ" => "泽塔",
-                        "η" | "Η" => "艾塔",
-                        "θ" | "Θ" => "西塔",
-                        "ι" | "Ι" => "约塔",
-                        "κ" | "Κ" => "卡帕",
-                        "λ" | "Λ" => "兰姆达",
-                        "μ" | "Μ" => "缪",
-                        "ν" | "Ν" => "纽",
-                        "ξ" | "Ξ" => "克西",
-                        "ο" | "Ο" => "欧米克戈",
-                        "π" | "Π" => "派",
-                        "ρ" | "Ρ" => "罗",
-                        "σ" | "Σ" => "西格玛",
-                        "τ" | "Τ" => "套",
-                        "υ" | "Υ" => "宇普西龙",
-                        "φ" | "Φ" => "斐",
-                        "χ" | "Χ" => "希",
-                        "ψ" | "Ψ" => "普西",
-                        "ω" | "Ω" => "欧米伽",
-                        _ => {
-                            #[cfg(debug_assertions)]
-                            unreachable!("unknown: {:?} in greek", pair.as_str());
-                            #[cfg(not(debug_assertions))]
-                            ""
-                        }
-                    };
-                    builder.push_zh_word(txt);
-                }
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in word", pair.as_str());
-                }
+        fn year(builder: &mut PhoneBuilder, digits: &str) {
+            match digits.parse() {
+                Ok(year) => Self::push(builder, &normalize::en::year_to_words(year)),
+                Err(_) => Self::cardinal(builder, digits),
             }
         }
-        Ok(())
+
+        fn link(builder: &mut PhoneBuilder, _link: &str) {
+            builder.push_punctuation(SEPARATOR);
+        }
     }
+}
 
-    fn parse_ident(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::ident);
+#[cfg(feature = "enable_jp")]
+pub mod jp {
+    use super::*;
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::word => parse_word(pair, builder)?,
-                Rule::link => parse_link(pair, builder)?,
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in ident", pair.as_str());
-                }
-            }
-        }
-        Ok(())
+    pub struct Jp;
+
+    const DIGITS: [&str; 10] = [
+        "ぜろ",
+        "いち",
+        "に",
+        "さん",
+        "よん",
+        "ご",
+        "ろく",
+        "なな",
+        "はち",
+        "きゅう",
+    ];
+    /// (counter, reading after a number)
+    static COUNTERS: [(&str, &str); 21] = [
+        ("ヶ月", "かげつ"),
+        ("か月", "かげつ"),
+        ("ヵ月", "かげつ"),
+        ("カ月", "かげつ"),
+        ("つ", "つ"),
+        ("個", "こ"),
+        ("人", "にん"),
+        ("本", "ほん"),
+        ("枚", "まい"),
+        ("匹", "ひき"),
+        ("杯", "はい"),
+        ("回", "かい"),
+        ("円", "えん"),
+        ("歳", "さい"),
+        ("才", "さい"),
+        ("年", "ねん"),
+        ("月", "がつ"),
+        ("日", "にち"),
+        ("時", "じ"),
+        ("分", "ふん"),
+        ("秒", "びょう"),
+    ];
+
+    /// the last sound of a number turns into a small tsu before k, s, t and p
+    static SOKUON: [(&str, &str); 5] = [
+        ("いち", "いっ"),
+        ("ろく", "ろっ"),
+        ("はち", "はっ"),
+        ("じゅう", "じゅっ"),
+        ("ひゃく", "ひゃっ"),
+    ];
+
+    /// the counter at the start of the text
+    pub fn counter(text: &str) -> Option<&'static str> {
+        COUNTERS
+            .iter()
+            .map(|(c, _)| *c)
+            .find(|c| text.starts_with(c))
     }
 
-    pub fn parse_all(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::all);
+    fn counter_reading(counter: &str) -> &'static str {
+        COUNTERS
+            .iter()
+            .find(|(c, _)| *c == counter)
+            .map_or("", |(_, r)| *r)
+    }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::signs => parse_signs(pair, builder)?,
-                Rule::ident => parse_ident(pair, builder)?,
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in all", pair.as_str());
-                }
+    fn with_ending(num: &str, endings: &[(&str, &str)]) -> String {
+        for (end, rep) in endings {
+            if let Some(head) = num.strip_suffix(end) {
+                return format!("{}{}", head, rep);
             }
         }
-        Ok(())
+        num.to_string()
     }
-}
 
-pub mod en {
-    use crate::text::PhoneBuilder;
+    fn below_10000(n: u64, before_unit: bool) -> String {
+        let mut s = String::new();
+        match n / 1000 {
+            0 => {}
+            1 if before_unit => s.push_str("いっせん"),
+            1 => s.push_str("せん"),
+            3 => s.push_str("さんぜん"),
+            8 => s.push_str("はっせん"),
+            d => {
+                s.push_str(DIGITS[d as usize]);
+                s.push_str("せん");
+            }
+        }
+        match n / 100 % 10 {
+            0 => {}
+            1 => s.push_str("ひゃく"),
+            3 => s.push_str("さんびゃく"),
+            6 => s.push_str("ろっぴゃく"),
+            8 => s.push_str("はっぴゃく"),
+            d => {
+                s.push_str(DIGITS[d as usize]);
+                s.push_str("ひゃく");
+            }
+        }
+        match n / 10 % 10 {
+            0 => {}
+            1 => s.push_str("じゅう"),
+            d => {
+                s.push_str(DIGITS[d as usize]);
+                s.push_str("じゅう");
+            }
+        }
+        match n % 10 {
+            0 => {}
+            d => s.push_str(DIGITS[d as usize]),
+        }
+        s
+    }
 
-    use super::super::SEPARATOR;
-    use super::*;
-    use pest::iterators::Pair;
-
-    fn parse_pn(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::pn);
-        match pair.as_str() {
-            "+" => builder.push_en_word("plus"),
-            "-" => builder.push_en_word("minus"),
-            "*" | "×" => builder.push_en_word("times"),
-            "/" | "÷" => {
-                builder.push_en_word("divided");
-                builder.push_punctuation(SEPARATOR);
-                builder.push_en_word("by");
+    /// 300 -> さんびゃく, 1000000000000 -> いっちょう
+    pub fn cardinal_to_kana(n: u64) -> String {
+        if n == 0 {
+            return DIGITS[0].to_string();
+        }
+        let mut s = String::new();
+        let mut rest = n;
+        for (unit, kana) in [
+            (10u64.pow(16), "けい"),
+            (10u64.pow(12), "ちょう"),
+            (10u64.pow(8), "おく"),
+            (10u64.pow(4), "まん"),
+        ] {
+            let group = rest / unit;
+            rest %= unit;
+            if group == 0 {
+                continue;
             }
-            "=" => builder.push_en_word("is"),
-            _ => {
-                #[cfg(debug_assertions)]
-                unreachable!("unknown: {:?} in pn", pair);
+            let group = below_10000(group, true);
+            if unit >= 10u64.pow(12) {
+                s.push_str(&with_ending(
+                    &group,
+                    &[
+                        ("いち", "いっ"),
+                        ("はち", "はっ"),
+                        ("じゅう", "じゅっ"),
+                        ("ひゃく", "ひゃっ"),
+                    ],
+                ));
+            } else {
+                s.push_str(&group);
             }
+            s.push_str(kana);
+        }
+        if rest > 0 {
+            s.push_str(&below_10000(rest, false));
         }
-        builder.push_punctuation(SEPARATOR);
+        s
+    }
 
-        Ok(())
+    /// 本, 匹, 杯 and 分 start with p after a small tsu, and 本, 匹, 杯 start with b after ん
+    fn h_row(num: &str, reading: &str) -> String {
+        let mut chars = reading.chars();
+        let head = chars.next().unwrap_or_default();
+        let tail = chars.as_str();
+        let (b, p) = match head {
+            'ほ' => ('ぼ', 'ぽ'),
+            'ひ' => ('び', 'ぴ'),
+            'は' => ('ば', 'ぱ'),
+            'ふ' => ('ぶ', 'ぷ'),
+            _ => return format!("{}{}", num, reading),
+        };
+        let sokuon = with_ending(num, &SOKUON);
+        let head = if sokuon != num || (num.ends_with('ん') && head == 'ふ') {
+            p
+        } else if num.ends_with('ん') && !num.ends_with("よん") {
+            b
+        } else {
+            head
+        };
+        format!("{}{}{}", sokuon, head, tail)
     }
 
-    fn parse_flag(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::flag);
-        match pair.as_str() {
-            "-" => builder.push_en_word("negative"),
-            _ => {
-                #[cfg(debug_assertions)]
-                unreachable!("unknown: {:?} in flag", pair);
+    /// read a number with the counter after it, 3本 -> さんぼん, 1日 -> ついたち
+    pub fn counter_to_kana(n: u64, counter: &str) -> String {
+        static TSU: [&str; 10] = [
+            "ひとつ",
+            "ふたつ",
+            "みっつ",
+            "よっつ",
+            "いつつ",
+            "むっつ",
+            "ななつ",
+            "やっつ",
+            "ここのつ",
+            "とお",
+        ];
+        static DAYS: [&str; 10] = [
+            "ついたち",
+            "ふつか",
+            "みっか",
+            "よっか",
+            "いつか",
+            "むいか",
+            "なのか",
+            "ようか",
+            "ここのか",
+            "とおか",
+        ];
+        let num = cardinal_to_kana(n);
+        let reading = counter_reading(counter);
+        match (counter, n) {
+            ("つ", 1..=10) => TSU[n as usize - 1].to_string(),
+            ("つ", _) => num,
+            ("人", 1) => "ひとり".to_string(),
+            ("人", 2) => "ふたり".to_string(),
+            ("日", 1..=10) => DAYS[n as usize - 1].to_string(),
+            ("日", 14 | 24) => with_ending(&num, &[("よん", "よっか")]),
+            ("日", 20) => "はつか".to_string(),
+            ("月", 4) => "しがつ".to_string(),
+            ("月", 7) => "しちがつ".to_string(),
+            ("月", 9) => "くがつ".to_string(),
+            ("歳" | "才", 20) => "はたち".to_string(),
+            ("人" | "年" | "円", _) => with_ending(&num, &[("よん", "よ")]) + reading,
+            ("時", _) => {
+                with_ending(&num, &[("よん", "よ"), ("なな", "しち"), ("きゅう", "く")]) + reading
+            }
+            ("歳" | "才", _) => {
+                with_ending(
+                    &num,
+                    &[("いち", "いっ"), ("はち", "はっ"), ("じゅう", "じゅっ")],
+                ) + reading
             }
+            ("個" | "回" | "ヶ月" | "か月" | "ヵ月" | "カ月", _) => {
+                with_ending(&num, &SOKUON) + reading
+            }
+            ("本" | "匹" | "杯" | "分", _) => h_row(&num, reading),
+            _ => num + reading,
         }
-        builder.push_punctuation(SEPARATOR);
-        Ok(())
     }
 
-    fn parse_percent(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::percent);
-        // percent = { (decimals|integer)~"%" }
+    impl Reader for Jp {
+        const DIGITS: [&'static str; 10] = DIGITS;
+        const ZERO: &'static str = "れい";
+        const POINT: &'static str = "てん";
+        const PERCENT: Affix = Affix::After("ぱーせんと");
+        const OVER: &'static str = "ぶんの";
+        const SCI: (&'static str, &'static str) = ("かけるじゅうの", "じょう");
+        const SQUARED: Option<(&'static str, &'static str)> = None;
+        const POWER: (&'static str, &'static str) = ("の", "じょう");
+        const ROOTS: (&'static str, &'static str) = ("るーと", "りっぽうこん");
+        const TO: &'static str = "から";
+        const VERSUS: &'static str = "たい";
+        const MINUS: &'static str = "ひく";
+
+        fn push(builder: &mut PhoneBuilder, words: &str) {
+            builder.push_jp_word(words);
+        }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::decimals => parse_decimals(pair, builder)?,
-                Rule::integer => {
-                    parse_integer(pair, builder, true)?;
-                }
+        fn cardinal(builder: &mut PhoneBuilder, digits: &str) {
+            match digits.parse::<u64>() {
+                Ok(n) => builder.push_jp_word(&cardinal_to_kana(n)),
+                Err(_) => Self::digits(builder, digits),
+            }
+        }
+
+        fn operator(op: &str) -> &'static str {
+            match op {
+                "+" => "ぷらす",
+                "-" => "まいなす",
+                "*" | "×" => "かける",
+                "/" | "÷" => "わる",
+                "=" => "いこーる",
+                ">=" | "≥" => "だいなりいこーる",
+                "<=" | "≤" => "しょうなりいこーる",
+                "!=" | "≠" => "のっといこーる",
+                ">" => "だいなり",
+                "<" => "しょうなり",
+                "≈" => "にありーいこーる",
+                "±" => "ぷらすまいなす",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in expr", pair.as_str());
+                    unreachable!("unknown: {:?} in pn", op);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        builder.push_en_word("percent");
-        builder.push_punctuation(SEPARATOR);
 
-        Ok(())
-    }
+        fn flag(flag: &str) -> &'static str {
+            match flag {
+                "±" => "ぷらすまいなす",
+                _ => "まいなす",
+            }
+        }
 
-    fn parse_integer(pair: Pair<Rule>, builder: &mut PhoneBuilder, unit: bool) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::integer);
-        if unit && let Ok(r) = num2en::str_to_words(pair.as_str()) {
-            r.split(&[' ', '-']).for_each(|s| {
-                builder.push_en_word(s);
-                builder.push_punctuation(SEPARATOR);
-            });
-            return Ok(());
-        }
-
-        let inner = pair.into_inner();
-        for pair in inner {
-            let txt = match pair.as_str() {
-                "0" => "zero",
-                "1" => "one",
-                "2" => "two",
-                "3" => "three",
-                "4" => "four",
-                "5" => "five",
-                "6" => "six",
-                "7" => "seven",
-                "8" => "eight",
-                "9" => "nine",
-                n => {
+        fn greek_name(c: &str) -> &'static str {
+            match c {
+                "α" | "Α" => "あるふぁ",
+                "β" | "Β" => "べーた",
+                "γ" | "Γ" => "がんま",
+                "δ" | "Δ" => "でるた",
+                "ε" | "Ε" => "いぷしろん",
+                "ζ" | "Ζ" => "ぜーた",
+                "η" | "Η" => "いーた",
+                "θ" | "Θ" => "しーた",
+                "ι" | "Ι" => "いおた",
+                "κ" | "Κ" => "かっぱ",
+                "λ" | "Λ" => "らむだ",
+                "μ" | "Μ" => "みゅー",
+                "ν" | "Ν" => "にゅー",
+                "ξ" | "Ξ" => "くさい",
+                "ο" | "Ο" => "おみくろん",
+                "π" | "Π" => "ぱい",
+                "ρ" | "Ρ" => "ろー",
+                "σ" | "Σ" | "ς" => "しぐま",
+                "τ" | "Τ" => "たう",
+                "υ" | "Υ" => "うぷしろん",
+                "φ" | "Φ" => "ふぁい",
+                "χ" | "Χ" => "かい",
+                "ψ" | "Ψ" => "ぷさい",
+                "ω" | "Ω" => "おめが",
+                _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in integer", n);
+                    unreachable!("unknown: {:?} in greek", c);
                     #[cfg(not(debug_assertions))]
-                    n
+                    ""
                 }
-            };
-            builder.push_en_word(txt);
-            builder.push_punctuation(SEPARATOR);
+            }
         }
 
-        Ok(())
-    }
+        fn clock(builder: &mut PhoneBuilder, parts: &[u64]) {
+            for (&n, counter) in parts.iter().zip(["時", "分", "秒"]) {
+                if n > 0 || counter == "時" {
+                    builder.push_jp_word(&counter_to_kana(n, counter));
+                }
+            }
+        }
 
-    fn parse_decimals(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::decimals);
-        if let Ok(r) = num2en::str_to_words(pair.as_str()) {
-            r.split(&[' ', '-']).for_each(|s| {
-                builder.push_en_word(s);
-                builder.push_punctuation(SEPARATOR);
-            });
-            return Ok(());
+        fn letter(builder: &mut PhoneBuilder, s: &str) {
+            builder.push_jp_word(s);
         }
 
-        let mut inner = pair.into_inner().rev();
-        let f_part = inner.next().unwrap();
-        if let Some(i_part) = inner.next() {
-            parse_integer(i_part, builder, true)?;
-        } else {
-            builder.push_en_word("zero");
-            builder.push_punctuation(SEPARATOR);
+        fn serial_link(builder: &mut PhoneBuilder) {
+            builder.push_jp_word("の");
         }
-        builder.push_en_word("point");
-        builder.push_punctuation(SEPARATOR);
 
-        parse_integer(f_part, builder, false)?;
+        fn counted(builder: &mut PhoneBuilder, n: u64, counter: &str) {
+            builder.push_jp_word(&counter_to_kana(n, counter));
+        }
 
-        Ok(())
+        fn counter(builder: &mut PhoneBuilder, counter: &str) {
+            builder.push_jp_word(counter_reading(counter));
+        }
     }
+}
 
-    fn parse_fractional(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::fractional);
+#[cfg(feature = "enable_ko")]
+pub mod ko {
+    use super::*;
 
-        let mut inner = pair.into_inner();
-        let numerator = inner.next().unwrap();
-        let denominator = inner.next().unwrap();
-        parse_integer(numerator, builder, true)?;
-        builder.push_en_word("over");
-        builder.push_punctuation(SEPARATOR);
-        parse_integer(denominator, builder, true)?;
+    pub struct Ko;
+
+    const DIGITS: [&str; 10] = ["영", "일", "이", "삼", "사", "오", "육", "칠", "팔", "구"];
+
+    /// native numbers before a counter, 한 개 and 스무 살
+    static NATIVE_ONES: [&str; 10] = [
+        "", "한", "두", "세", "네", "다섯", "여섯", "일곱", "여덟", "아홉",
+    ];
+    static NATIVE_TENS: [&str; 10] = [
+        "", "열", "스물", "서른", "마흔", "쉰", "예순", "일흔", "여든", "아흔",
+    ];
+
+    /// (counter, read with native numbers)
+    static COUNTERS: [(&str, bool); 12] = [
+        ("개월", false),
+        ("마리", true),
+        ("사람", true),
+        ("그루", true),
+        ("개", true),
+        ("명", true),
+        ("살", true),
+        ("시", true),
+        ("잔", true),
+        ("권", true),
+        ("채", true),
+        ("월", false),
+    ];
+
+    /// the counter at the start of the text
+    pub fn counter(text: &str) -> Option<&'static str> {
+        COUNTERS
+            .iter()
+            .map(|(c, _)| *c)
+            .find(|c| text.starts_with(c))
+    }
 
-        Ok(())
+    fn below_10000(n: u64) -> String {
+        let mut s = String::new();
+        for (d, unit) in [(n / 1000, "천"), (n / 100 % 10, "백"), (n / 10 % 10, "십")] {
+            match d {
+                0 => {}
+                1 => s.push_str(unit),
+                d => {
+                    s.push_str(DIGITS[d as usize]);
+                    s.push_str(unit);
+                }
+            }
+        }
+        match n % 10 {
+            0 => {}
+            d => s.push_str(DIGITS[d as usize]),
+        }
+        s
     }
 
-    fn parse_num(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::num);
+    /// sino-korean reading, 10000 -> 만, 300 -> 삼백
+    pub fn cardinal_to_hangul(n: u64) -> String {
+        if n == 0 {
+            return DIGITS[0].to_string();
+        }
+        let mut s = String::new();
+        let mut rest = n;
+        for (unit, hangul) in [
+            (10u64.pow(16), "경"),
+            (10u64.pow(12), "조"),
+            (10u64.pow(8), "억"),
+            (10u64.pow(4), "만"),
+        ] {
+            let group = rest / unit;
+            rest %= unit;
+            match group {
+                0 => continue,
+                1 if hangul == "만" => {}
+                group => s.push_str(&below_10000(group)),
+            }
+            s.push_str(hangul);
+        }
+        s.push_str(&below_10000(rest));
+        s
+    }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::flag => parse_flag(pair, builder)?,
-                Rule::percent => parse_percent(pair, builder)?,
-                Rule::decimals => {
-                    parse_decimals(pair, builder)?;
-                }
-                Rule::fractional => {
-                    parse_fractional(pair, builder)?;
-                }
-                Rule::integer => {
-                    parse_integer(pair, builder, true)?;
-                }
+    fn native_to_hangul(n: u64) -> String {
+        match n {
+            20 => "스무".to_string(),
+            n => format!(
+                "{}{}",
+                NATIVE_TENS[(n / 10) as usize],
+                NATIVE_ONES[(n % 10) as usize]
+            ),
+        }
+    }
+
+    /// read a number with the counter after it, 3개 -> 세 개, 6월 -> 유월
+    pub fn counter_to_hangul(n: u64, counter: &str) -> String {
+        let native = COUNTERS.iter().any(|(c, native)| *c == counter && *native);
+        match (counter, n) {
+            ("월", 6) => "유월".to_string(),
+            ("월", 10) => "시월".to_string(),
+            ("월", _) => cardinal_to_hangul(n) + counter,
+            (_, 1..=99) if native => format!("{} {counter}", native_to_hangul(n)),
+            _ => format!("{} {counter}", cardinal_to_hangul(n)),
+        }
+    }
+
+    fn digits_to_hangul(digits: &str, zero: &str) -> String {
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| if d == 0 { zero } else { DIGITS[d as usize] })
+            .collect()
+    }
+
+    impl Reader for Ko {
+        const DIGITS: [&'static str; 10] = DIGITS;
+        const POINT: &'static str = "점";
+        const PERCENT: Affix = Affix::After("퍼센트");
+        const OVER: &'static str = "분의";
+        const SCI: (&'static str, &'static str) = ("곱하기 십의", "제곱");
+        const SQUARED: Option<(&'static str, &'static str)> = Some(("제곱", "세제곱"));
+        const POWER: (&'static str, &'static str) = ("의", "제곱");
+        const ROOTS: (&'static str, &'static str) = ("루트", "세제곱근");
+        const TO: &'static str = "에서";
+        const VERSUS: &'static str = "대";
+        const MINUS: &'static str = "빼기";
+
+        fn push(builder: &mut PhoneBuilder, words: &str) {
+            builder.push_ko_word(words);
+            builder.push_punctuation(" ");
+        }
+
+        fn cardinal(builder: &mut PhoneBuilder, digits: &str) {
+            match digits.parse::<u64>() {
+                Ok(n) => Self::push(builder, &cardinal_to_hangul(n)),
+                Err(_) => Self::digits(builder, digits),
+            }
+        }
+
+        fn operator(op: &str) -> &'static str {
+            match op {
+                "+" => "더하기",
+                "-" => "빼기",
+                "*" | "×" => "곱하기",
+                "/" | "÷" => "나누기",
+                "=" => "는",
+                ">=" | "≥" => "크거나 같다",
+                "<=" | "≤" => "작거나 같다",
+                "!=" | "≠" => "같지 않다",
+                ">" => "크다",
+                "<" => "작다",
+                "≈" => "약",
+                "±" => "플러스마이너스",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in num", pair.as_str());
+                    unreachable!("unknown: {:?} in pn", op);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        Ok(())
-    }
 
-    fn parse_signs(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::signs);
+        fn flag(flag: &str) -> &'static str {
+            match flag {
+                "±" => "플러스마이너스",
+                _ => "마이너스",
+            }
+        }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::num => parse_num(pair, builder)?,
-                Rule::pn => parse_pn(pair, builder)?,
-                Rule::word => {
-                    println!("word: {:?}", pair.as_str());
-                }
+        fn greek_name(c: &str) -> &'static str {
+            match c {
+                "α" | "Α" => "알파",
+                "β" | "Β" => "베타",
+                "γ" | "Γ" => "감마",
+                "δ" | "Δ" => "델타",
+                "ε" | "Ε" => "엡실론",
+                "ζ" | "Ζ" => "제타",
+                "η" | "Η" => "에타",
+                "θ" | "Θ" => "세타",
+                "ι" | "Ι" => "요타",
+                "κ" | "Κ" => "카파",
+                "λ" | "Λ" => "람다",
+                "μ" | "Μ" => "뮤",
+                "ν" | "Ν" => "뉴",
+                "ξ" | "Ξ" => "크시",
+                "ο" | "Ο" => "오미크론",
+                "π" | "Π" => "파이",
+                "ρ" | "Ρ" => "로",
+                "σ" | "Σ" | "ς" => "시그마",
+                "τ" | "Τ" => "타우",
+                "υ" | "Υ" => "입실론",
+                "φ" | "Φ" => "피",
+                "χ" | "Χ" => "카이",
+                "ψ" | "Ψ" => "프사이",
+                "ω" | "Ω" => "오메가",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in expr", pair.as_str());
+                    unreachable!("unknown: {:?} in greek", c);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        Ok(())
+
+        fn clock(builder: &mut PhoneBuilder, parts: &[u64]) {
+            for (i, &n) in parts.iter().enumerate() {
+                match i {
+                    0 => Self::push(builder, &counter_to_hangul(n, "시")),
+                    _ if n == 0 => {}
+                    1 => Self::push(builder, &(cardinal_to_hangul(n) + "분")),
+                    _ => Self::push(builder, &(cardinal_to_hangul(n) + "초")),
+                }
+            }
+        }
+
+        /// 0 is 공 in phone and serial numbers
+        fn digits(builder: &mut PhoneBuilder, digits: &str) {
+            Self::push(builder, &digits_to_hangul(digits, "공"));
+        }
+
+        /// one word, 3.14 -> 삼점일사
+        fn decimals(builder: &mut PhoneBuilder, text: &str) {
+            let (i_part, f_part) = text.split_once('.').unwrap_or((text, ""));
+            let mut s = match i_part.parse::<u64>() {
+                Ok(n) => cardinal_to_hangul(n),
+                Err(_) if i_part.is_empty() => DIGITS[0].to_string(),
+                Err(_) => digits_to_hangul(i_part, DIGITS[0]),
+            };
+            if !f_part.is_empty() {
+                s.push_str(Self::POINT);
+                s.push_str(&digits_to_hangul(f_part, DIGITS[0]));
+            }
+            Self::push(builder, &s);
+        }
+
+        /// 3/4 -> 사분의 삼
+        fn fraction(builder: &mut PhoneBuilder, numerator: &str, denominator: &str) {
+            let denominator = denominator.parse::<u64>().unwrap_or_default();
+            Self::push(builder, &(cardinal_to_hangul(denominator) + Self::OVER));
+            Self::cardinal(builder, numerator);
+        }
+
+        fn letter(builder: &mut PhoneBuilder, s: &str) {
+            Self::push(builder, s);
+        }
+
+        fn link(_builder: &mut PhoneBuilder, _link: &str) {}
+
+        fn counted(builder: &mut PhoneBuilder, n: u64, counter: &str) {
+            builder.push_ko_word(&counter_to_hangul(n, counter));
+        }
+
+        fn counter(builder: &mut PhoneBuilder, counter: &str) {
+            builder.push_ko_word(counter);
+        }
     }
+}
+
+#[cfg(feature = "enable_yue")]
+pub mod yue {
+    use super::en::En;
+    use super::*;
+
+    pub struct Yue;
 
-    fn parse_link(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::link);
+    const DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
 
-        builder.push_punctuation(SEPARATOR);
+    static COUNTERS: [&str; 34] = [
+        "個", "个", "隻", "只", "條", "条", "張", "张", "本", "次", "件", "位", "杯", "碗", "架",
+        "部", "間", "间", "首", "封", "份", "種", "种", "歲", "岁", "年", "號", "号", "日", "月",
+        "點", "点", "蚊", "秒",
+    ];
 
-        Ok(())
+    /// the counter at the start of the text
+    pub fn counter(text: &str) -> Option<&'static str> {
+        COUNTERS.iter().copied().find(|c| text.starts_with(c))
     }
 
-    fn parse_word(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::word);
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::digit => {
-                    let txt = match pair.as_str() {
-                        "0" => "zero",
-                        "1" => "one",
-                        "2" => "two",
-                        "3" => "three",
-                        "4" => "four",
-                        "5" => "five",
-                        "6" => "six",
-                        "7" => "seven",
-                        "8" => "eight",
-                        "9" => "nine",
-                        n => {
-                            #[cfg(debug_assertions)]
-                            unreachable!("unknown: {:?} in integer", n);
-                            #[cfg(not(debug_assertions))]
-                            n
-                        }
-                    };
-                    builder.push_en_word(txt);
-                    builder.push_punctuation(SEPARATOR);
-                }
-                Rule::alpha => {
-                    let txt = pair.as_str();
-                    builder.push_en_word(txt);
-                }
-                Rule::greek => {
-                    let txt = match pair.as_str() {
-                        "α" | "Α" => "alpha",
-                        "β" | "Β" => "beta",
-                        "γ" | "Γ" => "gamma",
-                        "δ" | "Δ" => "delta",
-                        "ε" | "Ε" => "epsilon",
-                        "ζ" | "Ζ" => "zeta",
-                        "η" | "Η" => "eta",
-                        "θ" | "Θ" => "theta",
-                        "ι" | "Ι" => "iota",
-                        "κ" | "Κ" => "kappa",
-                        "λ" | "Λ" => "lambda",
-                        "μ" | "Μ" => "mu",
-                        "ν" | "Ν" => "nu",
-                        "ξ" | "Ξ" => "xi",
-                        "ο" | "Ο" => "omicron",
-                        "π" | "Π" => "pi",
-                        "ρ" | "Ρ" => "rho",
-                        "σ" | "Σ" => "sigma",
-                        "τ" | "Τ" => "tau",
-                        "υ" | "Υ" => "upsilon",
-                        "φ" | "Φ" => "phi",
-                        "χ" | "Χ" => "chi",
-                        "ψ" | "Ψ" => "psi",
-                        "ω" | "Ω" => "omega",
-
-                        _ => {
-                            #[cfg(debug_assertions)]
-                            unreachable!("unknown: {:?} in greek", pair.as_str());
-                            #[cfg(not(debug_assertions))]
-                            ""
-                        }
-                    };
-                    builder.push_en_word(txt);
-                    builder.push_punctuation(SEPARATOR);
-                }
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in word", pair.as_str());
-                }
+    /// `leading` is true for the first group of the number, 十一 instead of 一十一 and 兩百
+    /// instead of 二百
+    fn below_10000(n: u64, leading: bool) -> String {
+        let mut s = String::new();
+        let mut zero = false;
+        for (d, unit) in [
+            (n / 1000, "千"),
+            (n / 100 % 10, "百"),
+            (n / 10 % 10, "十"),
+            (n % 10, ""),
+        ] {
+            if d == 0 {
+                zero = !s.is_empty();
+                continue;
             }
+            if zero {
+                s.push_str(DIGITS[0]);
+                zero = false;
+            }
+            match (d, unit) {
+                (1, "十") if leading && s.is_empty() => {}
+                (2, "千" | "百") if leading && s.is_empty() => s.push('兩'),
+                (d, _) => s.push_str(DIGITS[d as usize]),
+            }
+            s.push_str(unit);
         }
-        Ok(())
+        s
     }
 
-    fn parse_ident(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::ident);
+    /// 10005 -> 一萬零五, 20000 -> 兩萬
+    pub fn cardinal_to_han(n: u64) -> String {
+        if n == 0 {
+            return DIGITS[0].to_string();
+        }
+        let mut s = String::new();
+        let mut rest = n;
+        for (unit, han) in [
+            (10u64.pow(16), "京"),
+            (10u64.pow(12), "兆"),
+            (10u64.pow(8), "億"),
+            (10u64.pow(4), "萬"),
+            (1, ""),
+        ] {
+            let group = rest / unit;
+            rest %= unit;
+            if group == 0 {
+                continue;
+            }
+            if !s.is_empty() && group < 1000 && !s.ends_with(DIGITS[0]) {
+                s.push_str(DIGITS[0]);
+            }
+            match group {
+                2 if s.is_empty() && !han.is_empty() => s.push('兩'),
+                group => s.push_str(&below_10000(group, s.is_empty())),
+            }
+            s.push_str(han);
+        }
+        s
+    }
+
+    /// 2個 -> 兩個, but 2號 and 2月 keep 二, a year before 年 is read digit by digit
+    pub fn counter_to_han(n: u64, counter: &str) -> String {
+        match (n, counter) {
+            (1000.., "年") => digits_to_han(&n.to_string()) + counter,
+            (_, "號" | "号" | "日" | "月") => cardinal_to_han(n) + counter,
+            (2, _) => "兩".to_string() + counter,
+            _ => cardinal_to_han(n) + counter,
+        }
+    }
+
+    fn digits_to_han(digits: &str) -> String {
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| DIGITS[d as usize])
+            .collect()
+    }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::word => parse_word(pair, builder)?,
-                Rule::link => parse_link(pair, builder)?,
+    impl Reader for Yue {
+        const DIGITS: [&'static str; 10] = DIGITS;
+        const POINT: &'static str = "點";
+        const PERCENT: Affix = Affix::Before("百分之");
+        const OVER: &'static str = "分之";
+        const SCI: (&'static str, &'static str) = ("乘十嘅", "次方");
+        const SQUARED: Option<(&'static str, &'static str)> = Some(("嘅平方", "嘅立方"));
+        const POWER: (&'static str, &'static str) = ("嘅", "次方");
+        const ROOTS: (&'static str, &'static str) = ("根號", "三次根號");
+        const TO: &'static str = "至";
+        const VERSUS: &'static str = "比";
+        const MINUS: &'static str = "減";
+
+        fn push(builder: &mut PhoneBuilder, words: &str) {
+            builder.push_yue_word(words);
+        }
+
+        fn cardinal(builder: &mut PhoneBuilder, digits: &str) {
+            match digits.parse::<u64>() {
+                Ok(n) => builder.push_yue_word(&cardinal_to_han(n)),
+                Err(_) => Self::digits(builder, digits),
+            }
+        }
+
+        fn operator(op: &str) -> &'static str {
+            match op {
+                "+" => "加",
+                "-" => "減",
+                "*" | "×" => "乘",
+                "/" | "÷" => "除以",
+                "=" => "等於",
+                ">=" | "≥" => "大過或等於",
+                "<=" | "≤" => "細過或等於",
+                "!=" | "≠" => "唔等於",
+                ">" => "大過",
+                "<" => "細過",
+                "≈" => "約等於",
+                "±" => "正負",
                 _ => {
                     #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in ident", pair.as_str());
+                    unreachable!("unknown: {:?} in pn", op);
+                    #[cfg(not(debug_assertions))]
+                    ""
                 }
             }
         }
-        Ok(())
-    }
 
-    pub fn parse_all(pair: Pair<Rule>, builder: &mut PhoneBuilder) -> Result<()> {
-        assert_eq!(pair.as_rule(), Rule::all);
+        fn flag(flag: &str) -> &'static str {
+            match flag {
+                "±" => "正負",
+                _ => "負",
+            }
+        }
 
-        let inner = pair.into_inner();
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::signs => parse_signs(pair, builder)?,
-                Rule::ident => parse_ident(pair, builder)?,
-                _ => {
-                    #[cfg(debug_assertions)]
-                    unreachable!("unknown: {:?} in all", pair.as_str());
+        /// greek letters are read by their english names in hong kong
+        fn greek_name(c: &str) -> &'static str {
+            En::greek_name(c)
+        }
+
+        /// 10:05 -> 十點零五分, 2:00 -> 兩點
+        fn clock(builder: &mut PhoneBuilder, parts: &[u64]) {
+            for (i, &n) in parts.iter().enumerate() {
+                match (i, n) {
+                    (0, n) => builder.push_yue_word(&counter_to_han(n, "點")),
+                    (_, 0) => {}
+                    (i, 1..10) => {
+                        builder.push_yue_word(&(DIGITS[0].to_string() + DIGITS[n as usize]));
+                        builder.push_yue_word(["分", "秒"][i - 1]);
+                    }
+                    (i, n) => {
+                        builder.push_yue_word(&cardinal_to_han(n));
+                        builder.push_yue_word(["分", "秒"][i - 1]);
+                    }
                 }
             }
         }
-        Ok(())
+
+        /// 2020 -> 二零二零
+        fn year(builder: &mut PhoneBuilder, digits: &str) {
+            Self::digits(builder, digits);
+        }
+
+        fn greek(builder: &mut PhoneBuilder, c: &str) {
+            builder.push_en_word(Self::greek_name(c));
+        }
+
+        fn counted(builder: &mut PhoneBuilder, n: u64, counter: &str) {
+            builder.push_yue_word(&counter_to_han(n, counter));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{EnWord, Sentence};
+    use pest::Parser;
+
+    /// the text that `R` reads `num` into, one sentence after another
+    fn read<R: Reader>(num: &str, hint: NumHint) -> String {
+        let mut builder = PhoneBuilder::new(false);
+        for pair in ExprParser::parse(Rule::all, num).unwrap() {
+            parse_all::<R>(pair, &mut builder, hint).unwrap();
+        }
+        let texts = builder.sentence.iter().map(|s| match s {
+            Sentence::Zh(zh) => zh.zh_text.clone(),
+            Sentence::En(en) => en
+                .en_text
+                .iter()
+                .filter_map(|w| match w {
+                    EnWord::Word(w) => Some(w.as_str()),
+                    EnWord::A => Some("a"),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            #[cfg(feature = "enable_jp")]
+            Sentence::Jp(jp) => jp.text.clone(),
+            #[cfg(feature = "enable_ko")]
+            Sentence::Ko(ko) => ko.text.clone(),
+            #[cfg(feature = "enable_yue")]
+            Sentence::Yue(yue) => yue.text.clone(),
+            Sentence::Num(n) => n.num_text.clone(),
+        });
+        texts.collect::<Vec<_>>().join("|").trim().to_string()
+    }
+
+    #[test]
+    fn zh() {
+        let zh = |num| read::<zh::Zh>(num, NumHint::Auto);
+        assert_eq!(zh("3.14"), "三点一四");
+        assert_eq!(zh("-5"), "负五");
+        assert_eq!(zh("50%"), "百分之五十");
+        assert_eq!(zh("3/4"), "四分之三");
+        assert_eq!(zh("2008"), "二千零八");
+        assert_eq!(zh("10~20"), "一十到二十");
+        assert_eq!(zh("12:30"), "一十二点三十分");
+        assert_eq!(zh("1.2e-5"), "一点二乘十的负五次方");
+        assert_eq!(zh("√9"), "根号九");
+        assert_eq!(read::<zh::Zh>("2:1", NumHint::Score), "二比一");
+        assert_eq!(read::<zh::Zh>("1:2", NumHint::Ratio), "一比二");
+    }
+
+    #[test]
+    fn en() {
+        let en = |num| read::<en::En>(num, NumHint::Auto);
+        assert_eq!(en("3.14"), "three point one four");
+        assert_eq!(en("-5"), "negative five");
+        assert_eq!(en("50%"), "fifty percent");
+        assert_eq!(en("3/4"), "three over four");
+        assert_eq!(en("10~20"), "ten to twenty");
+        assert_eq!(en("12:30"), "twelve thirty");
+        assert_eq!(en("x^2"), "x squared");
+        assert_eq!(en("√9"), "the square root of nine");
+        assert_eq!(en("A1"), "A one");
+        assert_eq!(read::<en::En>("2:1", NumHint::Score), "two to one");
+    }
+
+    #[cfg(feature = "enable_jp")]
+    #[test]
+    fn jp_cardinal() {
+        assert_eq!(jp::cardinal_to_kana(300), "さんびゃく");
+        assert_eq!(jp::cardinal_to_kana(600), "ろっぴゃく");
+        assert_eq!(jp::cardinal_to_kana(800), "はっぴゃく");
+        assert_eq!(jp::cardinal_to_kana(3000), "さんぜん");
+        assert_eq!(jp::cardinal_to_kana(8000), "はっせん");
+        assert_eq!(read::<jp::Jp>("2008", NumHint::Auto), "にせんはち");
+        assert_eq!(read::<jp::Jp>("3.14", NumHint::Auto), "さんてんいちよん");
+        assert_eq!(
+            read::<jp::Jp>("12:30", NumHint::Auto),
+            "じゅうにじさんじゅっぷん"
+        );
+    }
+
+    #[cfg(feature = "enable_jp")]
+    #[test]
+    fn jp_counters() {
+        assert_eq!(jp::counter("本の木"), Some("本"));
+        assert_eq!(jp::counter_to_kana(1, "本"), "いっぽん");
+        assert_eq!(jp::counter_to_kana(3, "本"), "さんぼん");
+        assert_eq!(jp::counter_to_kana(6, "本"), "ろっぽん");
+        assert_eq!(jp::counter_to_kana(1, "人"), "ひとり");
+        assert_eq!(jp::counter_to_kana(2, "人"), "ふたり");
+        assert_eq!(jp::counter_to_kana(4, "人"), "よにん");
+        assert_eq!(jp::counter_to_kana(4, "日"), "よっか");
+        assert_eq!(jp::counter_to_kana(20, "日"), "はつか");
+        assert_eq!(read::<jp::Jp>("3", NumHint::Counter("本")), "さんぼん");
+    }
+
+    #[cfg(feature = "enable_ko")]
+    #[test]
+    fn ko_sino() {
+        assert_eq!(ko::cardinal_to_hangul(0), "영");
+        assert_eq!(ko::cardinal_to_hangul(300), "삼백");
+        assert_eq!(ko::cardinal_to_hangul(1000), "천");
+        assert_eq!(ko::cardinal_to_hangul(10000), "만");
+        assert_eq!(ko::cardinal_to_hangul(12345), "만이천삼백사십오");
+        assert_eq!(ko::cardinal_to_hangul(100_000_000), "일억");
+        assert_eq!(read::<ko::Ko>("3.14", NumHint::Auto), "삼점일사");
+    }
+
+    #[cfg(feature = "enable_ko")]
+    #[test]
+    fn ko_counters() {
+        assert_eq!(ko::counter("개가 있다"), Some("개"));
+        assert_eq!(ko::counter("개월"), Some("개월"));
+        assert_eq!(ko::counter("사과"), None);
+        assert_eq!(ko::counter_to_hangul(1, "명"), "한 명");
+        assert_eq!(ko::counter_to_hangul(3, "개"), "세 개");
+        assert_eq!(ko::counter_to_hangul(20, "살"), "스무 살");
+        assert_eq!(ko::counter_to_hangul(21, "살"), "스물한 살");
+        assert_eq!(ko::counter_to_hangul(100, "개"), "백 개");
+        assert_eq!(ko::counter_to_hangul(3, "개월"), "삼 개월");
+        assert_eq!(ko::counter_to_hangul(6, "월"), "유월");
+        assert_eq!(ko::counter_to_hangul(10, "월"), "시월");
+        assert_eq!(ko::counter_to_hangul(3, "월"), "삼월");
+        assert_eq!(read::<ko::Ko>("3", NumHint::Counter("개")), "세 개");
+        assert_eq!(read::<ko::Ko>("3:30", NumHint::Auto), "세 시 삼십분");
+    }
+
+    #[cfg(feature = "enable_yue")]
+    #[test]
+    fn yue_cardinal() {
+        assert_eq!(yue::cardinal_to_han(2), "二");
+        assert_eq!(yue::cardinal_to_han(12), "十二");
+        assert_eq!(yue::cardinal_to_han(20), "二十");
+        assert_eq!(yue::cardinal_to_han(200), "兩百");
+        assert_eq!(yue::cardinal_to_han(2000), "兩千");
+        assert_eq!(yue::cardinal_to_han(20000), "兩萬");
+        assert_eq!(yue::cardinal_to_han(10005), "一萬零五");
+        assert_eq!(yue::cardinal_to_han(10u64.pow(16)), "一京");
+        assert_eq!(yue::cardinal_to_han(2 * 10u64.pow(16) + 5), "兩京零五");
+        assert_eq!(read::<yue::Yue>("3.5", NumHint::Auto), "三點五");
+    }
+
+    #[cfg(feature = "enable_yue")]
+    #[test]
+    fn yue_counters() {
+        assert_eq!(yue::counter("個蘋果"), Some("個"));
+        assert_eq!(yue::counter("蘋果"), None);
+        assert_eq!(yue::counter_to_han(2, "個"), "兩個");
+        assert_eq!(yue::counter_to_han(2, "號"), "二號");
+        assert_eq!(yue::counter_to_han(2, "月"), "二月");
+        assert_eq!(yue::counter_to_han(22, "個"), "二十二個");
+        assert_eq!(yue::counter_to_han(1997, "年"), "一九九七年");
+        assert_eq!(read::<yue::Yue>("2", NumHint::Counter("個")), "兩個");
     }
 }
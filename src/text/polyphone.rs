@@ -0,0 +1,182 @@
+//! User rules that pin the pinyin of chinese polyphonic chars, applied before the g2pw
+//! result. A rule file is a json list like:
+//!
+//! ```json
+//! [
+//!   { "word": "重庆", "pinyin": ["chong2", "qing4"] },
+//!   { "char": "长", "context": "长(大|高)", "pinyin": "zhang3" },
+//!   { "char": "行", "pos": "n", "pinyin": "hang2" }
+//! ]
+//! ```
+//!
+//! A char rule pins every occurrence unless it has a `context` regex the char must be
+//! inside, or a `pos` that must be a prefix of the jieba tag of its word. Later rules win.
+
+use crate::error::*;
+use crate::text::g2pw::{self, G2PWOut};
+use regex::Regex;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleDef {
+    Word {
+        word: String,
+        pinyin: Vec<String>,
+    },
+    Char {
+        char: char,
+        pinyin: String,
+        #[serde(default)]
+        context: Option<String>,
+        #[serde(default)]
+        pos: Option<String>,
+    },
+}
+
+#[derive(Debug)]
+enum Rule {
+    Word {
+        word: String,
+        pinyin: Vec<&'static str>,
+    },
+    Char {
+        char: char,
+        pinyin: &'static str,
+        context: Option<Regex>,
+        pos: Option<String>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct PolyphoneRules {
+    rules: Vec<Rule>,
+}
+
+impl PolyphoneRules {
+    pub fn load(path: &str) -> Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        let defs: Vec<RuleDef> =
+            serde_json::from_str(&s).map_err(|e| NihilityGsvError::Rule(format!("{path}: {e}")))?;
+        let rules = defs
+            .into_iter()
+            .map(Self::compile)
+            .collect::<Result<Vec<_>>>()?;
+        debug!("load {} polyphone rules from {}", rules.len(), path);
+        Ok(Self { rules })
+    }
+
+    fn compile(def: RuleDef) -> Result<Rule> {
+        let pinyin = |c: char, p: &str| {
+            g2pw::pinyin_label(c, p)
+                .ok_or_else(|| NihilityGsvError::Rule(format!("unknown pinyin {p} for {c}")))
+        };
+        match def {
+            RuleDef::Word { word, pinyin: p } => {
+                if word.chars().count() != p.len() {
+                    return Err(NihilityGsvError::Rule(format!(
+                        "{word} has {} pinyin",
+                        p.len()
+                    )));
+                }
+                let pinyin = word
+                    .chars()
+                    .zip(&p)
+                    .map(|(c, p)| pinyin(c, p))
+                    .collect::<Result<_>>()?;
+                Ok(Rule::Word { word, pinyin })
+            }
+            RuleDef::Char {
+                char,
+                pinyin: p,
+                context,
+                pos,
+            } => {
+                let context = context
+                    .map(|c| Regex::new(&c))
+                    .transpose()
+                    .map_err(|e| NihilityGsvError::Rule(e.to_string()))?;
+                Ok(Rule::Char {
+                    char,
+                    pinyin: pinyin(char, &p)?,
+                    context,
+                    pos,
+                })
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn need_tag(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|r| matches!(r, Rule::Char { pos: Some(_), .. }))
+    }
+
+    /// Pin the phones of `text`, one per char
+    pub fn apply(&self, jieba: &jieba_rs::Jieba, hmm: bool, text: &str, phones: &mut [G2PWOut]) {
+        if self.is_empty() {
+            return;
+        }
+        let starts = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        if starts.len() != phones.len() {
+            warn!(
+                "skip polyphone rules, {} chars but {} phones",
+                starts.len(),
+                phones.len()
+            );
+            return;
+        }
+        let index = |byte: usize| starts.binary_search(&byte).unwrap_or_default();
+
+        // the jieba tag of each char
+        let mut tags = vec![];
+        if self.need_tag() {
+            for t in jieba.tag(text, hmm) {
+                tags.extend(t.word.chars().map(|_| t.tag));
+            }
+        }
+
+        for rule in &self.rules {
+            match rule {
+                Rule::Word { word, pinyin } => {
+                    for (i, _) in text.match_indices(word.as_str()) {
+                        let i = index(i);
+                        phones[i..i + pinyin.len()]
+                            .iter_mut()
+                            .zip(pinyin)
+                            .for_each(|(out, p)| *out = G2PWOut::Pinyin(p));
+                    }
+                }
+                Rule::Char {
+                    char,
+                    pinyin,
+                    context,
+                    pos,
+                } => {
+                    let ranges: Vec<std::ops::Range<usize>> = match context {
+                        Some(re) => re.find_iter(text).map(|m| m.range()).collect(),
+                        None => std::iter::once(0..text.len()).collect(),
+                    };
+                    for range in ranges {
+                        for (i, c) in text[range.clone()].char_indices() {
+                            let i = index(range.start + i);
+                            if c != *char
+                                || pos.as_ref().is_some_and(|p| {
+                                    !tags.get(i).is_some_and(|t| t.starts_with(p.as_str()))
+                                })
+                            {
+                                continue;
+                            }
+                            phones[i] = G2PWOut::Pinyin(pinyin);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
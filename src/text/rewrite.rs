@@ -0,0 +1,57 @@
+//! Regex rewrites of the request text before the frontend reads it, like "k8s" -> "kubernetes"
+//! or dropping the `**` of markdown. The rules run in order, each on the output of the last.
+
+use super::TextLanguage;
+use crate::error::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewriteRule {
+    pub pattern: String,
+    /// `$1` and `${name}` refer to the groups of the pattern
+    #[serde(default)]
+    pub replace: String,
+    /// only text read in one of these languages, any when empty
+    #[serde(default)]
+    pub languages: Vec<TextLanguage>,
+    /// only text read by one of these voices, any when empty
+    #[serde(default)]
+    pub voices: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rewriter {
+    rules: Vec<(Regex, RewriteRule)>,
+}
+
+impl Rewriter {
+    pub fn new(rules: &[RewriteRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|re| (re, rule.clone()))
+                    .map_err(|e| NihilityGsvError::Rule(format!("{}: {}", rule.pattern, e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Apply the rules for `language` and `voice` to `text`
+    pub fn apply<'a>(&self, text: &'a str, language: TextLanguage, voice: &str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (re, rule) in &self.rules {
+            if (!rule.languages.is_empty() && !rule.languages.contains(&language))
+                || (!rule.voices.is_empty() && !rule.voices.iter().any(|v| v == voice))
+            {
+                continue;
+            }
+            if let Cow::Owned(s) = re.replace_all(&text, rule.replace.as_str()) {
+                text = Cow::Owned(s);
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,430 @@
+//! A practical subset of SSML. `speak`, `p`, `s`, `break`, `say-as`, `phoneme`, `sub`, `lang`,
+//! `prosody` and `voice` are read, the text of an unknown element is read as is. `phoneme` and
+//! `say-as interpret-as="characters"` become the inline markup of `text::markup`.
+
+use super::{TextLanguage, acronym, is_han};
+use crate::error::*;
+use lazy_static::lazy_static;
+use pest::Parser;
+use pest::iterators::Pair;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+#[derive(pest_derive::Parser)]
+#[grammar = "resource/ssml.pest"]
+struct SsmlParser;
+
+lazy_static! {
+    static ref RE_ENTITY: Regex =
+        Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|amp|lt|gt|quot|apos);").unwrap();
+    static ref RE_SPACE: Regex = Regex::new(r"\s+").unwrap();
+}
+
+static MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Applied to the audio after the inference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    /// speed, 2.0 is twice as fast
+    pub rate: f32,
+    /// gain, 1.0 keeps the volume
+    pub volume: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    /// the request language when not set
+    pub language: Option<TextLanguage>,
+    pub prosody: Prosody,
+    /// the selected voice when not set
+    pub voice: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Text(String, Style),
+    /// silence in seconds
+    Break(f32),
+}
+
+fn unescape(text: &str) -> Cow<'_, str> {
+    RE_ENTITY.replace_all(text, |caps: &regex::Captures| match &caps[1] {
+        "amp" => "&".to_string(),
+        "lt" => "<".to_string(),
+        "gt" => ">".to_string(),
+        "quot" => "\"".to_string(),
+        "apos" => "'".to_string(),
+        n => {
+            let code = match n.strip_prefix("#x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => n[1..].parse().ok(),
+            };
+            code.and_then(char::from_u32)
+                .map(String::from)
+                .unwrap_or_default()
+        }
+    })
+}
+
+/// `xml:lang` like "zh-CN", "en-US" or "ja"
+fn language(lang: &str) -> Option<TextLanguage> {
+    let lang = lang.to_ascii_lowercase();
+    let (primary, region) = lang.split_once(['-', '_']).unwrap_or((&lang, ""));
+    match (primary, region) {
+        ("yue", _) | ("zh", "hk" | "mo") => Some(TextLanguage::Yue),
+        ("zh" | "cmn", _) => Some(TextLanguage::Zh),
+        ("en", _) => Some(TextLanguage::En),
+        ("ja", _) => Some(TextLanguage::Ja),
+        ("ko", _) => Some(TextLanguage::Ko),
+        _ => None,
+    }
+}
+
+/// "500ms" or "1.5s"
+fn seconds(time: &str) -> Option<f32> {
+    let time = time.trim();
+    let seconds = match time.strip_suffix("ms") {
+        Some(ms) => ms.trim().parse::<f32>().ok()? / 1000.0,
+        None => time.strip_suffix('s')?.trim().parse().ok()?,
+    };
+    (seconds.is_finite() && seconds >= 0.0).then_some(seconds)
+}
+
+/// "150%" and "1.5" are absolute, "+50%" is relative to the current value
+fn relative(value: &str) -> Option<f32> {
+    let factor = match value.strip_suffix('%') {
+        Some(p) if p.starts_with(['+', '-']) => 1.0 + p.parse::<f32>().ok()? / 100.0,
+        Some(p) => p.parse::<f32>().ok()? / 100.0,
+        None => value.parse().ok()?,
+    };
+    (factor.is_finite() && factor >= 0.0).then_some(factor)
+}
+
+fn rate(rate: &str) -> Option<f32> {
+    match rate {
+        "x-slow" => Some(0.5),
+        "slow" => Some(0.75),
+        "medium" | "default" => Some(1.0),
+        "fast" => Some(1.25),
+        "x-fast" => Some(1.5),
+        r => relative(r),
+    }
+}
+
+/// "loud", "+6dB" or "50", a plain number is on the 0-100 scale of SSML 1.1
+fn volume(volume: &str) -> Option<f32> {
+    match volume {
+        "silent" => Some(0.0),
+        "x-soft" => Some(0.25),
+        "soft" => Some(0.5),
+        "medium" | "default" => Some(1.0),
+        "loud" => Some(1.5),
+        "x-loud" => Some(2.0),
+        v if v.ends_with("dB") => {
+            let db = v.trim_end_matches("dB").parse::<f32>().ok()?;
+            Some(10f32.powf(db / 20.0))
+        }
+        v if v.ends_with('%') => relative(v),
+        v => {
+            let n = v.parse::<f32>().ok()?;
+            let factor = if v.starts_with(['+', '-']) {
+                1.0 + n / 100.0
+            } else {
+                n / 100.0
+            };
+            (factor.is_finite() && factor >= 0.0).then_some(factor)
+        }
+    }
+}
+
+fn strength(strength: &str) -> Option<f32> {
+    match strength {
+        "none" => Some(0.0),
+        "x-weak" => Some(0.1),
+        "weak" => Some(0.25),
+        "medium" => Some(0.5),
+        "strong" => Some(0.75),
+        "x-strong" => Some(1.0),
+        _ => None,
+    }
+}
+
+fn digits(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// letters are spelled with their names, other chars are read one by one
+fn characters(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                format!("{{en:{}}}", acronym::letter_phones(c).join(" "))
+            } else {
+                c.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the groups of the number are read digit by digit with a pause between them
+fn telephone(text: &str) -> String {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|g| !g.is_empty())
+        .map(digits)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn ordinal_suffix(n: u32) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// `format` orders the fields like "ymd", "mdy", "dm" or "y", "2024-05-01" is read as
+/// 2024年5月1日 or "May 1st, 2024"
+fn date(text: &str, format: &str, zh: bool) -> Option<String> {
+    let numbers = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(str::parse::<u32>)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .ok()?;
+    if numbers.len() != format.len() {
+        return None;
+    }
+    let (mut year, mut month, mut day) = (None, None, None);
+    for (field, n) in format.chars().zip(numbers) {
+        match field {
+            'y' => year = Some(n),
+            'm' if (1..=12).contains(&n) => month = Some(n),
+            'd' if (1..=31).contains(&n) => day = Some(n),
+            _ => return None,
+        }
+    }
+    if zh {
+        let s = [(year, "年"), (month, "月"), (day, "日")]
+            .into_iter()
+            .filter_map(|(n, unit)| n.map(|n| format!("{n}{unit}")))
+            .collect();
+        return Some(s);
+    }
+    let month = month.map(|m| MONTHS[m as usize - 1]);
+    let day = day.map(|d| format!("{d}{}", ordinal_suffix(d)));
+    let s = match (month, day, year) {
+        (Some(m), Some(d), Some(y)) => format!("{m} {d}, {y}"),
+        (Some(m), Some(d), None) => format!("{m} {d}"),
+        (Some(m), None, Some(y)) => format!("{m} {y}"),
+        (None, Some(d), None) => format!("the {d}"),
+        (m, _, y) => [m.map(str::to_string), y.map(|y| y.to_string())]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    Some(s)
+}
+
+struct Walker {
+    parts: Vec<Part>,
+    /// the document has chinese chars, dates of an auto language are read in chinese
+    cjk: bool,
+}
+
+impl Walker {
+    fn push_text(&mut self, text: &str, style: &Style) {
+        let text = RE_SPACE.replace_all(text, " ");
+        match self.parts.last_mut() {
+            Some(Part::Text(t, s)) if s == style => {
+                let text = if t.ends_with(' ') {
+                    text.trim_start()
+                } else {
+                    &text
+                };
+                t.push_str(text)
+            }
+            _ if text.trim().is_empty() => {}
+            _ => self
+                .parts
+                .push(Part::Text(text.into_owned(), style.clone())),
+        }
+    }
+
+    fn say_as(&self, attrs: &HashMap<&str, Cow<'_, str>>, text: &str, style: &Style) -> String {
+        let interpret = attrs.get("interpret-as").map(|i| i.as_ref());
+        match interpret.unwrap_or_default() {
+            "characters" | "spell-out" | "verbatim" => characters(text),
+            "digits" => digits(text),
+            "telephone" => telephone(text),
+            "date" => {
+                let format = attrs.get("format").map_or("ymd", |f| f.as_ref());
+                let zh = match style.language {
+                    Some(TextLanguage::En | TextLanguage::Ko | TextLanguage::AllKo) => false,
+                    Some(TextLanguage::Auto) | None => self.cjk,
+                    Some(_) => true,
+                };
+                date(text, format, zh).unwrap_or_else(|| {
+                    warn!("can not read {:?} as a {} date", text, format);
+                    text.to_string()
+                })
+            }
+            i => {
+                warn!("unknown say-as interpret-as: {:?}", i);
+                text.to_string()
+            }
+        }
+    }
+
+    fn walk(&mut self, pair: Pair<'_, Rule>, style: &Style) {
+        match pair.as_rule() {
+            Rule::text => self.push_text(&unescape(pair.as_str()), style),
+            Rule::element | Rule::empty_element => self.element(pair, style),
+            _ => {}
+        }
+    }
+
+    fn element(&mut self, pair: Pair<'_, Rule>, style: &Style) {
+        let mut inner = pair.into_inner();
+        let name = inner.next().map_or("", |n| n.as_str());
+        let mut attrs = HashMap::new();
+        let mut children = vec![];
+        for p in inner {
+            if p.as_rule() == Rule::attr {
+                let mut attr = p.into_inner();
+                if let (Some(k), Some(v)) = (attr.next(), attr.next()) {
+                    attrs.insert(k.as_str(), unescape(v.as_str()));
+                }
+            } else {
+                children.push(p);
+            }
+        }
+        let text = || {
+            children
+                .iter()
+                .flat_map(|p| std::iter::once(p.clone()).chain(p.clone().into_inner().flatten()))
+                .filter(|p| p.as_rule() == Rule::text)
+                .map(|p| unescape(p.as_str()))
+                .collect::<String>()
+        };
+
+        let mut style = style.clone();
+        if let Some(lang) = attrs.get("xml:lang") {
+            match language(lang) {
+                Some(l) => style.language = Some(l),
+                None => warn!("unknown xml:lang {:?}, keep {:?}", lang, style.language),
+            }
+        }
+        match name {
+            "speak" | "p" | "s" | "lang" => {}
+            "voice" => style.voice = attrs.get("name").map(|n| n.to_string()),
+            "prosody" => {
+                if let Some(r) = attrs.get("rate") {
+                    match rate(r) {
+                        Some(r) => style.prosody.rate *= r,
+                        None => warn!("unknown prosody rate: {:?}", r),
+                    }
+                }
+                if let Some(v) = attrs.get("volume") {
+                    match volume(v) {
+                        Some(v) => style.prosody.volume *= v,
+                        None => warn!("unknown prosody volume: {:?}", v),
+                    }
+                }
+            }
+            "break" => {
+                let time = match (attrs.get("time"), attrs.get("strength")) {
+                    (Some(t), _) => seconds(t),
+                    (None, Some(s)) => strength(s),
+                    (None, None) => strength("medium"),
+                };
+                match time {
+                    Some(t) => self.parts.push(Part::Break(t)),
+                    None => warn!("skip break: {:?}", attrs),
+                }
+                return;
+            }
+            "say-as" => {
+                let text = self.say_as(&attrs, &text(), &style);
+                self.push_text(&text, &style);
+                return;
+            }
+            "phoneme" => {
+                let text = text();
+                let ph = attrs.get("ph").map_or("", |p| p.as_ref());
+                let alphabet = attrs.get("alphabet").map_or("", |a| a.as_ref());
+                let text = match alphabet.to_ascii_lowercase().as_str() {
+                    "pinyin" => format!("{}{{{}}}", text, ph),
+                    "arpabet" | "x-arpabet" | "cmu" => format!(" {{en:{}}} ", ph),
+                    a => {
+                        warn!("unknown phoneme alphabet: {:?}", a);
+                        text
+                    }
+                };
+                self.push_text(&text, &style);
+                return;
+            }
+            "sub" => {
+                let text = match attrs.get("alias") {
+                    Some(alias) => alias.to_string(),
+                    None => text(),
+                };
+                self.push_text(&text, &style);
+                return;
+            }
+            "audio" | "mark" | "desc" => return,
+            n => debug!("unknown ssml element {:?}, read its text", n),
+        }
+        for child in children {
+            self.walk(child, &style);
+        }
+    }
+}
+
+/// Parse an SSML document into texts and breaks, the texts of the same style are merged
+pub fn parse(ssml: &str) -> Result<Vec<Part>> {
+    let pairs =
+        SsmlParser::parse(Rule::ssml, ssml).map_err(|e| NihilityGsvError::Ssml(e.to_string()))?;
+    let mut walker = Walker {
+        parts: vec![],
+        cjk: ssml.chars().any(is_han),
+    };
+    for pair in pairs.flat_map(|p| p.into_inner()) {
+        walker.walk(pair, &Style::default());
+    }
+    debug!("ssml parts: {:?}", walker.parts);
+    Ok(walker.parts)
+}